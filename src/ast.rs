@@ -12,46 +12,384 @@ pub enum ASTNode {
     Block(Vec<ASTNode>),
     ObjectLiteral(Vec<(String, ASTNode)>),
     ArrayLiteral(Vec<ASTNode>),
+    // `position` is the line and column of the operator, used to place warnings
     BinaryOp {
         left: Box<ASTNode>,
         op: TokenKind,
         right: Box<ASTNode>,
+        position: (usize, usize),
     },
     UnaryOp {
         op: TokenKind,
         operand: Box<ASTNode>,
     },
+    // `i++` or `i--`, which evaluate to the value from before the update
+    PostfixOp {
+        op: TokenKind,
+        operand: Box<ASTNode>,
+    },
     VariableDeclaration {
         name: String,
         value: Box<ASTNode>,
     },
+    // let [a, b, ...rest] = value;
+    ArrayDestructuring {
+        names: Vec<String>,
+        rest: Option<String>,
+        value: Box<ASTNode>,
+    },
     IfStatement {
         condition: Box<ASTNode>,
         consequence: Box<ASTNode>,
         alternative: Option<Box<ASTNode>>,
     },
+    // `position` is the line and column of the opening parenthesis, or of `|>` for a pipe
     FunctionCall {
         callee: Box<ASTNode>,
         arguments: Vec<ASTNode>,
+        position: (usize, usize),
     },
     FunctionDeclaration {
         name: Option<String>,
         parameters: Vec<String>,
         body: Box<ASTNode>,
     },
+    // `class Name { fn method() {...} ... }`, each method a named FunctionDeclaration
+    ClassDeclaration {
+        name: String,
+        methods: Vec<ASTNode>,
+    },
+    // `new Class(arguments)`
+    New {
+        class: Box<ASTNode>,
+        arguments: Vec<ASTNode>,
+    },
+    // `import "path.pit";`, or `import "path.pit" as name;` to collect its definitions in an object
+    Import {
+        path: String,
+        alias: Option<String>,
+    },
     ReturnStatement(Box<ASTNode>),
+    // The label of the loop to leave, or None for the innermost one
+    Break(Option<String>),
+    Continue(Option<String>),
     MemberAccess {
         object: Box<ASTNode>,
         member: String,
     },
+    Index {
+        object: Box<ASTNode>,
+        index: Box<ASTNode>,
+    },
+    // `alternative` is the `else` block, run when the loop finishes without `break`
     WhileStatement {
+        label: Option<String>,
         condition: Box<ASTNode>,
         body: Box<ASTNode>,
+        alternative: Option<Box<ASTNode>>,
     },
     ForStatement {
+        label: Option<String>,
         start: Box<ASTNode>,
         condition: Box<ASTNode>,
         iter: Box<ASTNode>,
         body: Box<ASTNode>,
+        alternative: Option<Box<ASTNode>>,
+    },
+}
+
+impl ASTNode {
+    pub fn kind_name(&self) -> &'static str {
+        match self {
+            ASTNode::NumberLiteral(_) => "NumberLiteral",
+            ASTNode::StringLiteral(_) => "StringLiteral",
+            ASTNode::BooleanLiteral(_) => "BooleanLiteral",
+            ASTNode::NullLiteral => "NullLiteral",
+            ASTNode::Expression(_) => "Expression",
+            ASTNode::Variable(_) => "Variable",
+            ASTNode::Program(_) => "Program",
+            ASTNode::Block(_) => "Block",
+            ASTNode::ObjectLiteral(_) => "ObjectLiteral",
+            ASTNode::ArrayLiteral(_) => "ArrayLiteral",
+            ASTNode::BinaryOp { .. } => "BinaryOp",
+            ASTNode::UnaryOp { .. } => "UnaryOp",
+            ASTNode::PostfixOp { .. } => "PostfixOp",
+            ASTNode::VariableDeclaration { .. } => "VariableDeclaration",
+            ASTNode::ArrayDestructuring { .. } => "ArrayDestructuring",
+            ASTNode::IfStatement { .. } => "IfStatement",
+            ASTNode::FunctionCall { .. } => "FunctionCall",
+            ASTNode::FunctionDeclaration { .. } => "FunctionDeclaration",
+            ASTNode::ClassDeclaration { .. } => "ClassDeclaration",
+            ASTNode::New { .. } => "New",
+            ASTNode::Import { .. } => "Import",
+            ASTNode::ReturnStatement(_) => "ReturnStatement",
+            ASTNode::Break(_) => "Break",
+            ASTNode::Continue(_) => "Continue",
+            ASTNode::MemberAccess { .. } => "MemberAccess",
+            ASTNode::Index { .. } => "Index",
+            ASTNode::WhileStatement { .. } => "WhileStatement",
+            ASTNode::ForStatement { .. } => "ForStatement",
+        }
+    }
+}
+
+/// Replaces operations on literals with their result, so `2 * 3 + 1` becomes
+/// `NumberLiteral(7.0)`. Only operations that can't fail or warn are folded, and anything
+/// involving a variable or call is left for the evaluator.
+pub fn fold_constants(node: ASTNode) -> ASTNode {
+    let fold = |node: Box<ASTNode>| Box::new(fold_constants(*node));
+    let fold_all = |nodes: Vec<ASTNode>| nodes.into_iter().map(fold_constants).collect();
+    match node {
+        ASTNode::BinaryOp {
+            left,
+            op,
+            right,
+            position,
+        } => fold_binary_op(fold(left), op, fold(right), position),
+        ASTNode::UnaryOp { op, operand } => fold_unary_op(op, fold(operand)),
+        ASTNode::PostfixOp { op, operand } => ASTNode::PostfixOp {
+            op,
+            operand: fold(operand),
+        },
+        ASTNode::Expression(expr) => ASTNode::Expression(fold(expr)),
+        ASTNode::Program(statements) => ASTNode::Program(fold_all(statements)),
+        ASTNode::Block(statements) => ASTNode::Block(fold_all(statements)),
+        ASTNode::ArrayLiteral(elements) => ASTNode::ArrayLiteral(fold_all(elements)),
+        ASTNode::ObjectLiteral(properties) => ASTNode::ObjectLiteral(
+            properties
+                .into_iter()
+                .map(|(key, value)| (key, fold_constants(value)))
+                .collect(),
+        ),
+        ASTNode::VariableDeclaration { name, value } => ASTNode::VariableDeclaration {
+            name,
+            value: fold(value),
+        },
+        ASTNode::ArrayDestructuring { names, rest, value } => ASTNode::ArrayDestructuring {
+            names,
+            rest,
+            value: fold(value),
+        },
+        ASTNode::IfStatement {
+            condition,
+            consequence,
+            alternative,
+        } => ASTNode::IfStatement {
+            condition: fold(condition),
+            consequence: fold(consequence),
+            alternative: alternative.map(fold),
+        },
+        ASTNode::FunctionCall {
+            callee,
+            arguments,
+            position,
+        } => ASTNode::FunctionCall {
+            callee: fold(callee),
+            arguments: fold_all(arguments),
+            position,
+        },
+        ASTNode::FunctionDeclaration {
+            name,
+            parameters,
+            body,
+        } => ASTNode::FunctionDeclaration {
+            name,
+            parameters,
+            body: fold(body),
+        },
+        ASTNode::ClassDeclaration { name, methods } => ASTNode::ClassDeclaration {
+            name,
+            methods: fold_all(methods),
+        },
+        ASTNode::New { class, arguments } => ASTNode::New {
+            class: fold(class),
+            arguments: fold_all(arguments),
+        },
+        ASTNode::ReturnStatement(expr) => ASTNode::ReturnStatement(fold(expr)),
+        ASTNode::MemberAccess { object, member } => ASTNode::MemberAccess {
+            object: fold(object),
+            member,
+        },
+        ASTNode::Index { object, index } => ASTNode::Index {
+            object: fold(object),
+            index: fold(index),
+        },
+        ASTNode::WhileStatement {
+            label,
+            condition,
+            body,
+            alternative,
+        } => ASTNode::WhileStatement {
+            label,
+            condition: fold(condition),
+            body: fold(body),
+            alternative: alternative.map(fold),
+        },
+        ASTNode::ForStatement {
+            label,
+            start,
+            condition,
+            iter,
+            body,
+            alternative,
+        } => ASTNode::ForStatement {
+            label,
+            start: fold(start),
+            condition: fold(condition),
+            iter: fold(iter),
+            body: fold(body),
+            alternative: alternative.map(fold),
+        },
+        other => other,
+    }
+}
+
+fn fold_binary_op(
+    left: Box<ASTNode>,
+    op: TokenKind,
+    right: Box<ASTNode>,
+    position: (usize, usize),
+) -> ASTNode {
+    use ASTNode::{BooleanLiteral, NullLiteral, NumberLiteral, StringLiteral};
+    let folded = match (&*left, op, &*right) {
+        (NumberLiteral(a), _, NumberLiteral(b)) => match op {
+            TokenKind::Plus => Some(NumberLiteral(a + b)),
+            TokenKind::Minus => Some(NumberLiteral(a - b)),
+            TokenKind::Star => Some(NumberLiteral(a * b)),
+            TokenKind::Slash => Some(NumberLiteral(a / b)),
+            TokenKind::Mod => Some(NumberLiteral(a % b)),
+            TokenKind::Equal => Some(BooleanLiteral(a == b)),
+            TokenKind::NotEqual => Some(BooleanLiteral(a != b)),
+            TokenKind::Greater => Some(BooleanLiteral(a > b)),
+            TokenKind::GreaterEqual => Some(BooleanLiteral(a >= b)),
+            TokenKind::Less => Some(BooleanLiteral(a < b)),
+            TokenKind::LessEqual => Some(BooleanLiteral(a <= b)),
+            _ => None,
+        },
+        (StringLiteral(a), TokenKind::Plus, StringLiteral(b)) => Some(StringLiteral(a.clone() + b)),
+        (StringLiteral(a), TokenKind::Equal, StringLiteral(b)) => Some(BooleanLiteral(a == b)),
+        (StringLiteral(a), TokenKind::NotEqual, StringLiteral(b)) => Some(BooleanLiteral(a != b)),
+        (BooleanLiteral(a), TokenKind::Equal, BooleanLiteral(b)) => Some(BooleanLiteral(a == b)),
+        (BooleanLiteral(a), TokenKind::NotEqual, BooleanLiteral(b)) => Some(BooleanLiteral(a != b)),
+        // `&&` and `||` skip their right side when the left decides the result, so it can be
+        // dropped even if it isn't constant
+        (l, TokenKind::And, r) => match (literal_truthiness(l), literal_truthiness(r)) {
+            (Some(false), _) => Some(BooleanLiteral(false)),
+            (Some(true), Some(r)) => Some(BooleanLiteral(r)),
+            _ => None,
+        },
+        (l, TokenKind::Or, r) => match (literal_truthiness(l), literal_truthiness(r)) {
+            (Some(true), _) => Some(BooleanLiteral(true)),
+            (Some(false), Some(r)) => Some(BooleanLiteral(r)),
+            _ => None,
+        },
+        (NullLiteral, TokenKind::NullCoalesce, _) => Some((*right).clone()),
+        (l, TokenKind::NullCoalesce, _) if literal_truthiness(l).is_some() => Some((*left).clone()),
+        _ => None,
+    };
+    folded.unwrap_or(ASTNode::BinaryOp {
+        left,
+        op,
+        right,
+        position,
+    })
+}
+
+fn fold_unary_op(op: TokenKind, operand: Box<ASTNode>) -> ASTNode {
+    match (op, &*operand) {
+        (TokenKind::Minus, ASTNode::NumberLiteral(n)) => ASTNode::NumberLiteral(-n),
+        (TokenKind::Bang, literal) if literal_truthiness(literal).is_some() => {
+            ASTNode::BooleanLiteral(!literal_truthiness(literal).unwrap())
+        }
+        _ => ASTNode::UnaryOp { op, operand },
+    }
+}
+
+// Truthiness of a literal, matching `Value::is_truthy`, or None for anything else
+fn literal_truthiness(node: &ASTNode) -> Option<bool> {
+    match node {
+        ASTNode::BooleanLiteral(b) => Some(*b),
+        ASTNode::NumberLiteral(n) => Some(*n != 0.0),
+        ASTNode::StringLiteral(s) => Some(!s.is_empty()),
+        ASTNode::NullLiteral => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::tokenizer::tokenize;
+
+    // The folded form of the single expression statement in `source`
+    fn folded(source: &str) -> ASTNode {
+        let tokens = tokenize(source.to_string()).expect("tokenize");
+        match fold_constants(parse(&tokens).expect("parse")) {
+            ASTNode::Program(mut statements) if statements.len() == 1 => statements.remove(0),
+            other => panic!("expected one statement, got {:?}", other),
+        }
+    }
+
+    fn number(n: f64) -> ASTNode {
+        ASTNode::NumberLiteral(n)
+    }
+
+    #[test]
+    fn folds_arithmetic() {
+        assert_eq!(folded("2 * 3 + 1;"), number(7.0));
+        assert_eq!(folded("-(4 - 10) % 4;"), number(2.0));
+        assert_eq!(folded("1 + 2 < 4;"), ASTNode::BooleanLiteral(true));
+    }
+
+    #[test]
+    fn division_by_zero_keeps_the_evaluator_results() {
+        assert_eq!(folded("1 / 0;"), number(f64::INFINITY));
+        assert_eq!(folded("-1 / 0;"), number(f64::NEG_INFINITY));
+        match folded("5 % 0;") {
+            ASTNode::NumberLiteral(n) => assert!(n.is_nan()),
+            other => panic!("expected a number, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn folds_string_concatenation() {
+        assert_eq!(
+            folded("\"a\" + \"b\" + \"c\";"),
+            ASTNode::StringLiteral("abc".to_string())
+        );
+        assert_eq!(folded("\"a\" == \"a\";"), ASTNode::BooleanLiteral(true));
+    }
+
+    #[test]
+    fn folds_short_circuit_booleans() {
+        assert_eq!(folded("true && false;"), ASTNode::BooleanLiteral(false));
+        assert_eq!(folded("false || 1;"), ASTNode::BooleanLiteral(true));
+        // The left side decides the result, so the call on the right is dropped
+        assert_eq!(folded("false && f();"), ASTNode::BooleanLiteral(false));
+        assert_eq!(folded("true || f();"), ASTNode::BooleanLiteral(true));
+        assert_eq!(folded("null ?? 2;"), number(2.0));
+    }
+
+    #[test]
+    fn leaves_variables_and_calls_alone() {
+        // Only the constant operand is folded; `x` and `f()` stay for the evaluator
+        match folded("x + 2 * 3;") {
+            ASTNode::BinaryOp {
+                left, op, right, ..
+            } => {
+                assert_eq!(*left, ASTNode::Variable("x".to_string()));
+                assert_eq!(op, TokenKind::Plus);
+                assert_eq!(*right, number(6.0));
+            }
+            other => panic!("expected a binary op, got {:?}", other),
+        }
+        match folded("true && f();") {
+            ASTNode::BinaryOp { op, right, .. } => {
+                assert_eq!(op, TokenKind::And);
+                assert!(matches!(*right, ASTNode::FunctionCall { .. }));
+            }
+            other => panic!("expected a binary op, got {:?}", other),
+        }
     }
 }
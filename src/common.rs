@@ -18,6 +18,10 @@ impl TokenizerError {
             self.message, self.line, self.column
         )
     }
+    /// Like `as_message`, followed by the offending line of `source` with a caret under the column.
+    pub fn as_detailed_message(&self, source: &str) -> String {
+        self.as_message() + &source_excerpt(source, self.line, self.column)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -41,4 +45,86 @@ impl ParserError {
             self.message, self.line, self.column
         )
     }
+    /// Like `as_message`, followed by the offending line of `source` with a caret under the column.
+    pub fn as_detailed_message(&self, source: &str) -> String {
+        self.as_message() + &source_excerpt(source, self.line, self.column)
+    }
+}
+
+// Renders line `line` of `source` (1-based) with a `^` under `column`, e.g.
+//
+//   3 | let x = (1 + );
+//     |              ^
+//
+// Tabs before the column are kept so the caret lines up however they are displayed. Returns an
+// empty string if the line doesn't exist.
+fn source_excerpt(source: &str, line: usize, column: usize) -> String {
+    let Some(text) = source.lines().nth(line.wrapping_sub(1)) else {
+        return String::new();
+    };
+    let padding: String = text
+        .chars()
+        .take(column.saturating_sub(1))
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let gutter = " ".repeat(line.to_string().len());
+    format!("\n {} | {}\n {} | {}^", line, text, gutter, padding)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    pub message: String,
+    // Line and column of the operator or call that caused the warning, if it has one
+    pub position: Option<(usize, usize)>,
+}
+
+impl Warning {
+    pub fn new(message: &str, position: Option<(usize, usize)>) -> Self {
+        Self {
+            message: message.to_string(),
+            position,
+        }
+    }
+    pub fn as_message(&self) -> String {
+        match self.position {
+            Some((line, column)) => format!(
+                "Warning: {} at line {} column {}",
+                self.message, line, column
+            ),
+            None => format!("Warning: {}", self.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizer_errors_point_at_their_column() {
+        let error = TokenizerError::new("Invalid escape character \\q", 2, 12);
+        assert_eq!(
+            error.as_detailed_message("let a = 1;\n\tlet s = \"\\q\";"),
+            "Invalid escape character \\q at line 2 column 12\n 2 | \tlet s = \"\\q\";\n   | \t          ^"
+        );
+    }
+
+    #[test]
+    fn parser_errors_point_at_their_column() {
+        let source = format!("{}let x = (1 + );", "\n".repeat(9));
+        let error = ParserError::new("Unexpected token", 10, 14);
+        assert_eq!(
+            error.as_detailed_message(&source),
+            "Unexpected token at line 10 column 14\n 10 | let x = (1 + );\n    |              ^"
+        );
+    }
+
+    #[test]
+    fn errors_past_the_last_line_have_no_excerpt() {
+        let error = ParserError::new("Unexpected end of input", 3, 1);
+        assert_eq!(
+            error.as_detailed_message("let x ="),
+            "Unexpected end of input at line 3 column 1"
+        );
+    }
 }
@@ -6,6 +6,8 @@ pub enum EvalError {
     TypeError(String),
     ArgumentError(String),
     Runtime(String),
+    // Raised by `std.exit`, so a host running a script can end it without ending its own process
+    Exit(i32),
 }
 
 impl fmt::Display for EvalError {
@@ -15,6 +17,7 @@ impl fmt::Display for EvalError {
             EvalError::TypeError(msg) => write!(f, "Type error: {}", msg),
             EvalError::ArgumentError(msg) => write!(f, "Argument error: {}", msg),
             EvalError::Runtime(msg) => write!(f, "Runtime error: {}", msg),
+            EvalError::Exit(code) => write!(f, "Exited with code {}", code),
         }
     }
 }
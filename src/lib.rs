@@ -2,6 +2,8 @@ pub mod ast;
 pub mod errors;
 pub mod treewalk {
     pub mod evaluator;
+    #[cfg(feature = "regex")]
+    pub mod patterns;
     pub mod stdlib;
     pub mod value;
 }
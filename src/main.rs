@@ -1,10 +1,13 @@
-use pitlang::ast::ASTNode;
+use pitlang::ast::{self, ASTNode};
+use pitlang::common::Warning;
+use pitlang::errors::EvalError;
 use pitlang::parser;
 use pitlang::tokenizer;
 use pitlang::treewalk::evaluator;
 use std::env;
 use std::fs::File;
 use std::io::{BufReader, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
 
 fn get_file_contents(file_path: &str) -> Result<String, std::io::Error> {
     let file = File::open(file_path)?;
@@ -25,18 +28,29 @@ fn main() {
 
     let ast_arg = args.contains(&String::from("-ast"));
     let token_arg = args.contains(&String::from("-t"));
+    let warn_arg = args.contains(&String::from("-warn"));
+    let trace_arg = args.contains(&String::from("-trace"));
+    let test_arg = args.contains(&String::from("-test"));
+    let fold_arg = args.contains(&String::from("-O"));
 
     if args.contains(&String::from("-h")) {
-        println!("Usage: {} <file> [-t] [-ast] [-eval]", args[0]);
+        println!(
+            "Usage: {} <file> [-t] [-ast] [-eval] [-warn] [-trace] [-test] [-O]",
+            args[0]
+        );
         println!("\t-t: Tokenize only");
         println!("\t-ast: Print AST");
         println!("\t-eval: Evaluate AST");
+        println!("\t-warn: Report suspicious operations as warnings");
+        println!("\t-trace: Print a line for every statement, call and assignment");
+        println!("\t-test: Run every top-level `test_` function and report the results");
+        println!("\t-O: Fold constant expressions before running");
         return;
     }
 
     if args.contains(&String::from("-repl")) {
         let temp = Vec::new();
-        let mut evaluator = evaluator::TreeWalk::new(temp);
+        let mut evaluator = evaluator::TreeWalk::new(temp).with_warnings(warn_arg);
         let mut ast: ASTNode;
         loop {
             let mut input = String::new();
@@ -46,10 +60,10 @@ fn main() {
                 eprintln!("Error reading input: {}", e);
                 continue;
             }
-            let tokens = match tokenizer::tokenize(input) {
+            let tokens = match tokenizer::tokenize(input.clone()) {
                 Ok(t) => t,
                 Err(e) => {
-                    eprintln!("Tokenization error: {}", e.as_message());
+                    eprintln!("Tokenization error: {}", e.as_detailed_message(&input));
                     continue;
                 }
             };
@@ -58,7 +72,7 @@ fn main() {
                 Err(e) => {
                     eprintln!("Parsing error: ");
                     for error in e {
-                        eprintln!("{}", error.as_message());
+                        eprintln!("{}", error.as_detailed_message(&input));
                     }
                     continue;
                 }
@@ -72,7 +86,9 @@ fn main() {
                 println!("{:?}", ast);
             }
 
-            println!("{:?}", evaluator.evaluate(ast.clone()));
+            let result = panic::catch_unwind(AssertUnwindSafe(|| evaluator.evaluate(ast.clone())));
+            println!("{}", exit_on_request(result));
+            print_warnings(evaluator.take_warnings());
         }
     }
 
@@ -85,10 +101,10 @@ fn main() {
         }
     };
 
-    let tokens = match tokenizer::tokenize(contents) {
+    let tokens = match tokenizer::tokenize(contents.clone()) {
         Ok(t) => t,
         Err(e) => {
-            eprintln!("Tokenization error: {}", e.as_message());
+            eprintln!("Tokenization error: {}", e.as_detailed_message(&contents));
             return;
         }
     };
@@ -100,11 +116,12 @@ fn main() {
     }
 
     let ast: ASTNode = match parser::parse(tokens.as_slice()) {
+        Ok(a) if fold_arg => ast::fold_constants(a),
         Ok(a) => a,
         Err(e) => {
             eprintln!("Parsing error: ");
             for error in e {
-                eprintln!("{}", error.as_message());
+                eprintln!("{}", error.as_detailed_message(&contents));
             }
             return;
         }
@@ -112,5 +129,102 @@ fn main() {
     if ast_arg {
         println!("{:?}", ast);
     }
-    evaluator::evaluate(ast);
+    let mut evaluator = evaluator::TreeWalk::new(Vec::new())
+        .with_warnings(warn_arg)
+        .with_script_path(file_path);
+    if trace_arg {
+        evaluator.set_tracer(Box::new(|event| eprintln!("{}", event)));
+    }
+    if test_arg {
+        let passed = run_tests(&mut evaluator, ast);
+        print_warnings(evaluator.take_warnings());
+        if !passed {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    // Runtime errors panic, so catch the unwind long enough to report what led up to them
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        evaluator.evaluate(ast);
+    }));
+    print_warnings(evaluator.take_warnings());
+    exit_on_request(result);
+}
+
+// Ends the process if the script called `std.exit`, and otherwise passes the result through,
+// resuming any other unwind
+fn exit_on_request<T>(result: std::thread::Result<T>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(payload) => {
+            if let Some(EvalError::Exit(code)) = payload.downcast_ref::<EvalError>() {
+                std::process::exit(*code);
+            }
+            panic::resume_unwind(payload)
+        }
+    }
+}
+
+// Runs the program, then calls each top-level zero-argument function whose name starts with
+// `test_`, in declaration order. Returns whether every test passed.
+fn run_tests(evaluator: &mut evaluator::TreeWalk, ast: ASTNode) -> bool {
+    let test_names: Vec<String> = match &ast {
+        ASTNode::Program(statements) => statements
+            .iter()
+            .filter_map(|stmt| match stmt {
+                ASTNode::FunctionDeclaration {
+                    name: Some(name),
+                    parameters,
+                    ..
+                } if name.starts_with("test_") && parameters.is_empty() => Some(name.clone()),
+                _ => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    exit_on_request(panic::catch_unwind(AssertUnwindSafe(|| {
+        evaluator.evaluate(ast);
+    })));
+
+    // Failures are reported below, so keep the default hook from printing each panic
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let mut failed = 0;
+    for name in &test_names {
+        let Some(function) = evaluator.global(name) else {
+            continue;
+        };
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            evaluator.call_function(&function, Vec::new());
+        }));
+        match result {
+            Ok(()) => println!("PASS {}", name),
+            Err(e) if e.is::<EvalError>() => {
+                panic::set_hook(default_hook);
+                exit_on_request(Err::<(), _>(e));
+                return false;
+            }
+            Err(e) => {
+                failed += 1;
+                evaluator.reset_scope();
+                let message = e
+                    .downcast_ref::<String>()
+                    .map(String::as_str)
+                    .or_else(|| e.downcast_ref::<&str>().copied())
+                    .unwrap_or("test panicked");
+                println!("FAIL {}: {}", name, message);
+            }
+        }
+    }
+    panic::set_hook(default_hook);
+
+    println!("{} passed, {} failed", test_names.len() - failed, failed);
+    failed == 0
+}
+
+fn print_warnings(warnings: Vec<Warning>) {
+    for warning in warnings {
+        eprintln!("{}", warning.as_message());
+    }
 }
@@ -11,6 +11,14 @@ struct Parser<'a> {
     tokens: &'a [Token],
     current: usize,
     errors: Vec<ParserError>,
+    // Set after an error until the next statement starts, so one mistake isn't reported repeatedly
+    recovering: bool,
+    // Labels of the loops around the current statement within the current function, innermost
+    // last, to reject a `break` or `continue` that has nothing to leave
+    loop_labels: Vec<Option<String>>,
+    // Stands in for the current token once the tokens run out, so looking ahead never goes past
+    // the end even if the token stream has no EOF token of its own
+    eof: Token,
 }
 
 impl<'a> Parser<'a> {
@@ -19,28 +27,43 @@ impl<'a> Parser<'a> {
             tokens,
             current: 0,
             errors: Vec::new(),
+            recovering: false,
+            loop_labels: Vec::new(),
+            eof: match tokens.last() {
+                Some(last) if last.kind == TokenKind::EOF => last.clone(),
+                Some(last) => Token::new(TokenKind::EOF, String::new(), last.line, last.column),
+                None => Token::new(TokenKind::EOF, String::new(), 1, 1),
+            },
         }
     }
 
     fn synchronize_tokens(&mut self) {
-        while self.current < self.tokens.len() {
-            if self.tokens[self.current].kind == TokenKind::SemiColon {
-                self.advance();
-                return;
+        loop {
+            match self.peek().kind {
+                TokenKind::SemiColon => {
+                    self.advance();
+                    return;
+                }
+                // Leave these in place so the next statement (or the enclosing block) can parse them
+                kind if starts_statement(kind) => return,
+                TokenKind::RBrace | TokenKind::EOF => return,
+                _ => self.current += 1,
             }
-            self.current += 1;
         }
     }
 
     fn error(&mut self, message: &str, token: &Token) {
-        self.errors
-            .push(ParserError::new(message, token.line, token.column));
+        if !self.recovering {
+            self.errors
+                .push(ParserError::new(message, token.line, token.column));
+            self.recovering = true;
+        }
         self.synchronize_tokens();
     }
 
     fn parse_program(&mut self) -> Result<ASTNode, Vec<ParserError>> {
         let mut statements = Vec::new();
-        while self.current < self.tokens.len() && self.tokens[self.current].kind != TokenKind::EOF {
+        while !self.at_end() {
             statements.push(self.parse_statement());
         }
         if self.errors.is_empty() {
@@ -51,73 +74,156 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_statement(&mut self) -> ASTNode {
-        if self.current >= self.tokens.len() {
-            self.error(
-                "Unexpected end of input in statement",
-                &self.tokens[self.tokens.len() - 1],
-            );
+        if self.at_end() {
+            let token = self.peek().clone();
+            self.error("Unexpected end of input in statement", &token);
             return ASTNode::NullLiteral;
         }
 
-        let token = self.tokens[self.current].clone();
+        self.recovering = false;
+        let token = self.peek().clone();
         match token.kind {
             TokenKind::Let => self.parse_variable_declaration(),
             TokenKind::If => self.parse_if_statement(),
             TokenKind::Function => self.parse_function_declaration(true),
+            TokenKind::Class => self.parse_class_declaration(),
+            TokenKind::Import => self.parse_import(),
             TokenKind::Return => self.parse_return_statement(),
+            TokenKind::Break | TokenKind::Continue => self.parse_loop_control(),
             TokenKind::LBrace => self.parse_block(),
-            TokenKind::While => self.parse_while_statement(),
-            TokenKind::For => self.parse_for_statement(),
+            TokenKind::While => self.parse_while_statement(None),
+            TokenKind::For => self.parse_for_statement(None),
+            // `label: while ...` or `label: for ...`
+            TokenKind::Identifier
+                if self.tokens.get(self.current + 1).map(|t| t.kind) == Some(TokenKind::Colon)
+                    && matches!(
+                        self.tokens.get(self.current + 2).map(|t| t.kind),
+                        Some(TokenKind::While | TokenKind::For)
+                    ) =>
+            {
+                self.current += 2;
+                let label = Some(token.value);
+                if self.peek().kind == TokenKind::While {
+                    self.parse_while_statement(label)
+                } else {
+                    self.parse_for_statement(label)
+                }
+            }
             TokenKind::SemiColon => {
                 self.advance();
                 self.parse_statement()
             }
             _ => {
                 let expr = self.parse_expression(0);
-                if self.current < self.tokens.len()
-                    && self.tokens[self.current].kind == TokenKind::SemiColon
-                {
-                    self.expect(TokenKind::SemiColon);
+                if self.peek().kind == TokenKind::SemiColon {
+                    self.advance();
                 }
                 expr
             }
         }
     }
 
-    fn parse_for_statement(&mut self) -> ASTNode {
+    fn parse_for_statement(&mut self, label: Option<String>) -> ASTNode {
         self.expect(TokenKind::For);
         let pre = self.parse_statement();
         let cond = self.parse_expression(0);
         let iter = self.parse_statement();
-        let block = self.parse_block();
+        let block = self.parse_loop_body(&label, Self::parse_block);
         ASTNode::ForStatement {
+            label,
             start: Box::new(pre),
             condition: Box::new(cond),
             iter: Box::new(iter),
             body: Box::new(block),
+            alternative: self.parse_loop_else(),
         }
     }
 
-    fn parse_while_statement(&mut self) -> ASTNode {
+    fn parse_while_statement(&mut self, label: Option<String>) -> ASTNode {
         self.expect(TokenKind::While);
         let condition = self.parse_expression(0);
-        let body = Box::new(self.parse_statement());
+        let body = Box::new(self.parse_loop_body(&label, Self::parse_statement));
         ASTNode::WhileStatement {
+            label,
             condition: Box::new(condition),
             body,
+            alternative: self.parse_loop_else(),
+        }
+    }
+
+    fn parse_loop_body(
+        &mut self,
+        label: &Option<String>,
+        parse: fn(&mut Self) -> ASTNode,
+    ) -> ASTNode {
+        self.loop_labels.push(label.clone());
+        let body = parse(self);
+        self.loop_labels.pop();
+        body
+    }
+
+    fn parse_loop_else(&mut self) -> Option<Box<ASTNode>> {
+        if self.peek().kind == TokenKind::Else {
+            self.advance();
+            Some(Box::new(self.parse_statement()))
+        } else {
+            None
+        }
+    }
+
+    fn parse_loop_control(&mut self) -> ASTNode {
+        let token = self.advance().clone();
+        if self.loop_labels.is_empty() {
+            self.error(&format!("'{}' outside of a loop", token.value), &token);
+            return ASTNode::NullLiteral;
+        }
+        let next = self.peek().clone();
+        let label = match next.kind {
+            TokenKind::Identifier => {
+                self.advance();
+                if !self.loop_labels.contains(&Some(next.value.clone())) {
+                    self.error(&format!("Unknown loop label '{}'", next.value), &next);
+                    return ASTNode::NullLiteral;
+                }
+                Some(next.value)
+            }
+            _ => None,
+        };
+        self.expect_semicolon(&format!("'{}'", token.value));
+        if token.kind == TokenKind::Break {
+            ASTNode::Break(label)
+        } else {
+            ASTNode::Continue(label)
         }
     }
 
-    fn parse_parameters(&mut self) -> Vec<String> {
-        let mut parameters = Vec::new();
+    /// Parses a parenthesised parameter list. Repeating a name, or reusing the name of the
+    /// function being declared, is an error, since the later binding would hide the earlier one.
+    fn parse_parameters(&mut self, function_name: Option<&str>) -> Vec<String> {
+        let mut parameters: Vec<String> = Vec::new();
         self.expect(TokenKind::LParen);
-        while self.current < self.tokens.len()
-            && self.tokens[self.current].kind != TokenKind::RParen
-        {
-            parameters.push(self.advance().value.clone());
-            if self.current < self.tokens.len()
-                && self.tokens[self.current].kind == TokenKind::RParen
-            {
+        while !matches!(self.peek().kind, TokenKind::RParen | TokenKind::EOF) {
+            let token = self.advance().clone();
+            if token.kind != TokenKind::Identifier {
+                self.error(
+                    &format!("Expected a parameter name, got '{}'", token.value),
+                    &token,
+                );
+                break;
+            }
+            if parameters.contains(&token.value) {
+                self.error(&format!("Duplicate parameter '{}'", token.value), &token);
+            } else if function_name == Some(token.value.as_str()) {
+                self.error(
+                    &format!(
+                        "Parameter '{}' has the same name as its function",
+                        token.value
+                    ),
+                    &token,
+                );
+            }
+            parameters.push(token.value);
+            if matches!(self.peek().kind, TokenKind::RParen | TokenKind::EOF) {
                 break;
             }
             self.expect(TokenKind::Comma);
@@ -128,12 +234,18 @@ impl<'a> Parser<'a> {
 
     fn parse_return_statement(&mut self) -> ASTNode {
         self.expect(TokenKind::Return);
-        if self.tokens[self.current].kind == TokenKind::SemiColon {
-            self.expect(TokenKind::SemiColon);
-            return ASTNode::ReturnStatement(Box::new(ASTNode::NullLiteral));
+        match self.peek().kind {
+            TokenKind::SemiColon => {
+                self.advance();
+                return ASTNode::ReturnStatement(Box::new(ASTNode::NullLiteral));
+            }
+            TokenKind::RBrace | TokenKind::EOF => {
+                return ASTNode::ReturnStatement(Box::new(ASTNode::NullLiteral));
+            }
+            _ => {}
         }
         let returnee = self.parse_expression(0);
-        self.expect(TokenKind::SemiColon);
+        self.expect_semicolon("return statement");
         ASTNode::ReturnStatement(Box::new(returnee))
     }
 
@@ -146,48 +258,170 @@ impl<'a> Parser<'a> {
         } else {
             None
         };
-        let parameters = self.parse_parameters();
+        let parameters = self.parse_parameters(name.as_deref());
+        // Loops around the declaration can't be left from inside the function body
+        let loop_labels = std::mem::take(&mut self.loop_labels);
         let body = Box::new(self.parse_block());
+        self.loop_labels = loop_labels;
         ASTNode::FunctionDeclaration {
             name,
             parameters,
             body,
         }
     }
+    // A class body holds only method declarations. `constructor`, if declared, initialises new
+    // instances.
+    fn parse_class_declaration(&mut self) -> ASTNode {
+        self.expect(TokenKind::Class);
+        let token = self.advance().clone();
+        if token.kind != TokenKind::Identifier {
+            self.error(
+                &format!("Expected a class name, got '{}'", token.value),
+                &token,
+            );
+            return ASTNode::NullLiteral;
+        }
+        let mut methods: Vec<ASTNode> = Vec::new();
+        let mut names: Vec<String> = Vec::new();
+        self.expect(TokenKind::LBrace);
+        while !matches!(self.peek().kind, TokenKind::RBrace | TokenKind::EOF) {
+            let start = self.peek().clone();
+            if start.kind != TokenKind::Function {
+                let position = self.current;
+                self.error(
+                    &format!(
+                        "Expected a method declaration in class '{}', got '{}'",
+                        token.value, start.value
+                    ),
+                    &start,
+                );
+                // Recovery stops at keywords without consuming them, so skip the token here to
+                // be sure the loop moves on
+                if self.current == position {
+                    self.advance();
+                }
+                continue;
+            }
+            let method = self.parse_function_declaration(true);
+            if let ASTNode::FunctionDeclaration {
+                name: Some(name), ..
+            } = &method
+            {
+                if names.contains(name) {
+                    self.error(&format!("Duplicate method '{}'", name), &start);
+                }
+                names.push(name.clone());
+            }
+            methods.push(method);
+        }
+        self.expect(TokenKind::RBrace);
+        ASTNode::ClassDeclaration {
+            name: token.value,
+            methods,
+        }
+    }
+
+    fn parse_import(&mut self) -> ASTNode {
+        self.expect(TokenKind::Import);
+        let token = self.advance().clone();
+        if token.kind != TokenKind::String {
+            self.error(
+                &format!(
+                    "Expected a file path string after 'import', got '{}'",
+                    token.value
+                ),
+                &token,
+            );
+            return ASTNode::NullLiteral;
+        }
+        // `as` is only special here, so it stays usable as a variable name
+        let next = self.peek().clone();
+        let alias = if next.kind == TokenKind::Identifier && next.value == "as" {
+            self.advance();
+            let name = self.advance().clone();
+            if name.kind != TokenKind::Identifier {
+                self.error(
+                    &format!("Expected a module name after 'as', got '{}'", name.value),
+                    &name,
+                );
+                return ASTNode::NullLiteral;
+            }
+            Some(name.value)
+        } else {
+            None
+        };
+        self.expect_semicolon("import");
+        ASTNode::Import {
+            path: token.value,
+            alias,
+        }
+    }
+
     fn parse_variable_declaration(&mut self) -> ASTNode {
         self.expect(TokenKind::Let);
+        if self.peek().kind == TokenKind::LBrack {
+            let (names, rest) = self.parse_array_pattern();
+            self.expect(TokenKind::Assign);
+            let value = self.parse_expression(0);
+            self.expect_semicolon("variable declaration");
+            return ASTNode::ArrayDestructuring {
+                names,
+                rest,
+                value: Box::new(value),
+            };
+        }
         let name = self.advance().value.clone();
         self.expect(TokenKind::Assign);
         let value = self.parse_expression(0);
-        self.expect(TokenKind::SemiColon);
+        self.expect_semicolon("variable declaration");
         ASTNode::VariableDeclaration {
             name,
             value: Box::new(value),
         }
     }
 
-    fn parse_block(&mut self) -> ASTNode {
-        let mut statements = Vec::new();
-        self.expect(TokenKind::LBrace);
-
-        if self.current >= self.tokens.len() {
-            self.error(
-                "Unexpected end of input in block",
-                &self.tokens[self.tokens.len() - 1],
-            );
-            return ASTNode::Block(statements);
-        }
-
-        while self.tokens[self.current].kind != TokenKind::RBrace {
-            statements.push(self.parse_statement());
-
-            if self.current >= self.tokens.len() {
+    // The `[a, b, ...rest]` on the left of a destructuring `let`. A rest element must come last.
+    fn parse_array_pattern(&mut self) -> (Vec<String>, Option<String>) {
+        let mut names = Vec::new();
+        let mut rest = None;
+        self.expect(TokenKind::LBrack);
+        while !matches!(self.peek().kind, TokenKind::RBrack | TokenKind::EOF) {
+            let is_rest = self.peek().kind == TokenKind::Ellipsis;
+            if is_rest {
+                self.advance();
+            }
+            let token = self.advance().clone();
+            if token.kind != TokenKind::Identifier {
                 self.error(
-                    "Unexpected end of input in block",
-                    &self.tokens[self.tokens.len() - 1],
+                    &format!("Expected a name in array pattern, got '{}'", token.value),
+                    &token,
                 );
                 break;
             }
+            if is_rest {
+                rest = Some(token.value);
+                break;
+            }
+            names.push(token.value);
+            if matches!(self.peek().kind, TokenKind::RBrack | TokenKind::EOF) {
+                break;
+            }
+            self.expect(TokenKind::Comma);
+        }
+        self.expect(TokenKind::RBrack);
+        (names, rest)
+    }
+
+    fn parse_block(&mut self) -> ASTNode {
+        let mut statements = Vec::new();
+        self.expect(TokenKind::LBrace);
+        while !matches!(self.peek().kind, TokenKind::RBrace | TokenKind::EOF) {
+            statements.push(self.parse_statement());
+        }
+        if self.at_end() {
+            let token = self.peek().clone();
+            self.error("Unexpected end of input in block", &token);
+            return ASTNode::Block(statements);
         }
         self.expect(TokenKind::RBrace);
         ASTNode::Block(statements)
@@ -197,9 +431,7 @@ impl<'a> Parser<'a> {
         self.expect(TokenKind::If);
         let condition = self.parse_expression(0);
         let consequence = self.parse_statement();
-        let alternative = if self.current < self.tokens.len()
-            && self.tokens[self.current].kind == TokenKind::Else
-        {
+        let alternative = if self.peek().kind == TokenKind::Else {
             self.advance();
             Some(Box::new(self.parse_statement()))
         } else {
@@ -213,19 +445,29 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_expression(&mut self, precedence: u8) -> ASTNode {
-        if self.current >= self.tokens.len() {
-            self.error(
-                "Unexpected end of input in expression",
-                &self.tokens[self.tokens.len() - 1],
-            );
-            return ASTNode::NullLiteral;
-        }
-
         let mut left = self.parse_nud();
+        // The comparison operator that built `left`, if any. One written in parentheses doesn't
+        // count, so `(a < b) < c` is still allowed.
+        let mut comparison: Option<Token> = None;
 
-        while self.current < self.tokens.len()
-            && precedence < self.get_operator_precedence(&self.tokens[self.current].kind)
-        {
+        while precedence < self.get_operator_precedence(&self.peek().kind) {
+            let operator = self.peek().clone();
+            if is_comparison(operator.kind) {
+                if let Some(first) = &comparison {
+                    self.error(
+                        &format!(
+                            "Comparisons can't be chained: write `a {0} b && b {1} c` instead of `a {0} b {1} c`",
+                            first.value, operator.value
+                        ),
+                        &operator,
+                    );
+                    break;
+                }
+                comparison = Some(operator);
+            } else {
+                comparison = None;
+            }
+            // `parse_led` always consumes the operator token, so each pass moves forward.
             left = self.parse_led(left);
         }
 
@@ -235,6 +477,7 @@ impl<'a> Parser<'a> {
     fn parse_led(&mut self, left: ASTNode) -> ASTNode {
         let token = self.advance().clone();
         let precedence = self.get_operator_precedence(&token.kind);
+        let position = (token.line, token.column);
 
         match token.kind {
             TokenKind::Assign => {
@@ -243,28 +486,63 @@ impl<'a> Parser<'a> {
                     left: Box::new(left),
                     op: token.kind,
                     right: Box::new(right),
+                    position,
                 }
             }
+            // `x |> f(a)` becomes `f(x, a)`, and `x |> f` becomes `f(x)`
+            TokenKind::Pipe => match self.parse_expression(precedence) {
+                ASTNode::FunctionCall {
+                    callee,
+                    mut arguments,
+                    ..
+                } => {
+                    arguments.insert(0, left);
+                    ASTNode::FunctionCall {
+                        callee,
+                        arguments,
+                        position,
+                    }
+                }
+                right => ASTNode::FunctionCall {
+                    callee: Box::new(right),
+                    arguments: vec![left],
+                    position,
+                },
+            },
             TokenKind::LParen => {
                 let arguments = self.parse_arguments();
                 ASTNode::FunctionCall {
                     callee: Box::new(left), // Accept any ASTNode as callee
                     arguments,
+                    position,
                 }
             }
             TokenKind::Dot => {
-                let member = self.advance().value.clone();
+                let member = self.parse_member_name();
                 ASTNode::MemberAccess {
                     object: Box::new(left),
                     member,
                 }
             }
+            TokenKind::Inc | TokenKind::Dec => ASTNode::PostfixOp {
+                op: token.kind,
+                operand: Box::new(left),
+            },
+            TokenKind::LBrack => {
+                let index = self.parse_expression(0);
+                self.expect(TokenKind::RBrack);
+                ASTNode::Index {
+                    object: Box::new(left),
+                    index: Box::new(index),
+                }
+            }
             _ => {
                 let right = self.parse_expression(precedence);
                 ASTNode::BinaryOp {
                     left: Box::new(left),
                     op: token.kind,
                     right: Box::new(right),
+                    position,
                 }
             }
         }
@@ -272,16 +550,19 @@ impl<'a> Parser<'a> {
 
     fn parse_arguments(&mut self) -> Vec<ASTNode> {
         let mut arguments = Vec::new();
-        if self.tokens[self.current].kind != TokenKind::RParen {
+        if self.peek().kind != TokenKind::RParen {
             loop {
                 arguments.push(self.parse_expression(0));
-                if self.current >= self.tokens.len() {
+                // A statement keyword means the list was never closed
+                let kind = self.peek().kind;
+                if matches!(kind, TokenKind::RParen | TokenKind::EOF) || starts_statement(kind) {
                     break;
                 }
-                if self.tokens[self.current].kind == TokenKind::RParen {
+                self.expect(TokenKind::Comma);
+                // Allow a trailing comma before the closing parenthesis
+                if self.peek().kind == TokenKind::RParen {
                     break;
                 }
-                self.expect(TokenKind::Comma);
             }
         }
         self.expect(TokenKind::RParen);
@@ -304,11 +585,20 @@ impl<'a> Parser<'a> {
             TokenKind::String => ASTNode::StringLiteral(token.value.clone()),
             TokenKind::Identifier => ASTNode::Variable(token.value.clone()),
             TokenKind::Function => self.parse_function_declaration(false),
+            TokenKind::New => {
+                // Stop before the argument list, so `new a.B(1)` is `new (a.B)(1)`
+                let class = self.parse_expression(self.get_operator_precedence(&TokenKind::LParen));
+                self.expect(TokenKind::LParen);
+                ASTNode::New {
+                    class: Box::new(class),
+                    arguments: self.parse_arguments(),
+                }
+            }
             TokenKind::True => ASTNode::BooleanLiteral(true),
             TokenKind::False => ASTNode::BooleanLiteral(false),
             TokenKind::Null => ASTNode::NullLiteral,
             TokenKind::Dot => {
-                let member = self.advance().value.clone();
+                let member = self.parse_member_name();
                 ASTNode::MemberAccess {
                     object: Box::new(self.parse_nud()),
                     member,
@@ -322,12 +612,12 @@ impl<'a> Parser<'a> {
             TokenKind::LBrace => {
                 let mut properties: Vec<(String, ASTNode)> = Vec::new();
 
-                while self.tokens[self.current].kind != TokenKind::RBrace {
+                while !matches!(self.peek().kind, TokenKind::RBrace | TokenKind::EOF) {
                     let key = self.advance().value.clone();
                     self.expect(TokenKind::Colon);
                     let value = self.parse_expression(0);
                     properties.push((key, value));
-                    if self.tokens[self.current].kind == TokenKind::Comma {
+                    if self.peek().kind == TokenKind::Comma {
                         self.advance();
                     }
                 }
@@ -337,9 +627,11 @@ impl<'a> Parser<'a> {
             TokenKind::LBrack => {
                 let mut elements: Vec<ASTNode> = Vec::new();
 
-                while self.tokens[self.current].kind != TokenKind::RBrack {
+                while !matches!(self.peek().kind, TokenKind::RBrack | TokenKind::EOF)
+                    && !starts_statement(self.peek().kind)
+                {
                     elements.push(self.parse_expression(0));
-                    if self.tokens[self.current].kind == TokenKind::Comma {
+                    if self.peek().kind == TokenKind::Comma {
                         self.advance();
                     }
                 }
@@ -363,61 +655,392 @@ impl<'a> Parser<'a> {
                 operand: Box::new(self.parse_expression(3)),
             },
             _ => {
-                let kind = token.kind;
                 let token = token.clone();
-                self.error(&format!("Unexpected token: {:?}", kind), &token);
+                let message = if token.kind == TokenKind::EOF {
+                    "Unexpected end of input".to_string()
+                } else {
+                    format!(
+                        "Unexpected token {:?} '{}' in `{}`",
+                        token.kind,
+                        token.value,
+                        self.snippet(self.current - 1)
+                    )
+                };
+                // A keyword here usually means the expression was left unfinished, as in
+                // `let x = 1 +` followed by `if ...`, so let the next statement start with it
+                if starts_statement(token.kind) {
+                    self.current -= 1;
+                }
+                self.error(&message, &token);
                 ASTNode::NullLiteral
             }
         }
     }
 
+    // The name after a `.`, which may be any token but the end of input
+    fn parse_member_name(&mut self) -> String {
+        let token = self.advance().clone();
+        if token.kind == TokenKind::EOF {
+            self.error("Unexpected end of input after '.'", &token);
+        }
+        token.value
+    }
+
+    // The token at `index` preceded by up to three tokens from the same line, to show where an
+    // error happened
+    fn snippet(&self, index: usize) -> String {
+        let line = self.tokens[index].line;
+        let start = (index.saturating_sub(3)..index)
+            .find(|&i| self.tokens[i].line == line)
+            .unwrap_or(index);
+        self.tokens[start..=index]
+            .iter()
+            .map(|token| match token.kind {
+                TokenKind::String => format!("{:?}", token.value),
+                _ => token.value.clone(),
+            })
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
     fn get_operator_precedence(&self, kind: &TokenKind) -> u8 {
         match kind {
-            TokenKind::Assign => 1,
-            TokenKind::Inc | TokenKind::Dec => 2,
+            TokenKind::Assign
+            | TokenKind::PlusAssign
+            | TokenKind::MinusAssign
+            | TokenKind::StarAssign
+            | TokenKind::SlashAssign
+            | TokenKind::ModAssign => 1,
+            TokenKind::Pipe => 2,
             TokenKind::Or => 3,
-            TokenKind::And => 4,
-            TokenKind::BitAnd => 5,
-            TokenKind::BitXor => 6,
-            TokenKind::BitOr => 7,
-            TokenKind::Equal | TokenKind::NotEqual => 8,
+            TokenKind::NullCoalesce => 4,
+            TokenKind::And => 5,
+            TokenKind::BitAnd => 6,
+            TokenKind::BitXor => 7,
+            TokenKind::BitOr => 8,
+            TokenKind::Equal | TokenKind::NotEqual => 9,
             TokenKind::Less
             | TokenKind::LessEqual
             | TokenKind::Greater
-            | TokenKind::GreaterEqual => 9,
-            TokenKind::Plus | TokenKind::Minus => 10,
-            TokenKind::Star | TokenKind::Mod | TokenKind::Slash => 11,
-            TokenKind::LParen => 12,
-            TokenKind::Dot => 13,
+            | TokenKind::GreaterEqual => 10,
+            TokenKind::Plus | TokenKind::Minus => 11,
+            TokenKind::Star | TokenKind::Mod | TokenKind::Slash => 12,
+            TokenKind::LParen => 13,
+            TokenKind::Dot | TokenKind::LBrack => 14,
+            // Postfix `++` and `--` apply to the whole `a.b[c]` chain before them
+            TokenKind::Inc | TokenKind::Dec => 15,
             _ => 0,
         }
     }
 
+    fn peek(&self) -> &Token {
+        self.tokens.get(self.current).unwrap_or(&self.eof)
+    }
+
+    fn at_end(&self) -> bool {
+        self.peek().kind == TokenKind::EOF
+    }
+
+    // Consumes and returns the current token. At the end of input this keeps returning the EOF
+    // token without moving, so it is up to the caller to notice it and report an error.
     fn advance(&mut self) -> &Token {
-        if self.current < self.tokens.len() {
-            let token = &self.tokens[self.current];
-            self.current += 1;
-            token
-        } else {
-            // Return a dummy EOF token or handle the error
-            self.error(
-                "Unexpected end of input",
-                &self.tokens[self.tokens.len() - 1],
-            );
-            &self.tokens[self.tokens.len() - 1] // Return the last token to avoid panic
+        if self.at_end() {
+            return &self.eof;
         }
+        self.current += 1;
+        &self.tokens[self.current - 1]
     }
 
+    // A missing `;` is reported at the end of the statement it belongs to, and nothing is skipped
+    // so the following statement still parses. The last statement of a block or file may omit it.
+    fn expect_semicolon(&mut self, after: &str) {
+        match self.peek().kind {
+            TokenKind::SemiColon => {
+                self.advance();
+                return;
+            }
+            TokenKind::RBrace | TokenKind::EOF => return,
+            _ => {}
+        }
+        if !self.recovering {
+            // The current token isn't the end of input, so there is one before it
+            let token = &self.tokens[self.current - 1];
+            self.errors.push(ParserError::new(
+                &format!("Expected ';' after {}", after),
+                token.line,
+                token.column,
+            ));
+        }
+    }
+
+    // Consumes a token of the given kind, or reports an error without consuming anything. At the
+    // end of input the error is recorded once and parsing winds down instead of looping.
     fn expect(&mut self, kind: TokenKind) {
-        if self.current >= self.tokens.len() || self.tokens[self.current].kind != kind {
-            let token = if self.current < self.tokens.len() {
-                &self.tokens[self.current]
-            } else {
-                &self.tokens[self.tokens.len() - 1]
-            };
-            self.error(&format!("Expected token: {:?}", kind), token);
-        } else {
+        if self.peek().kind == kind {
             self.advance();
+        } else if self.at_end() {
+            let token = self.peek().clone();
+            self.error(
+                &format!("Unexpected end of input, expected {:?}", kind),
+                &token,
+            );
+        } else {
+            let token = self.peek().clone();
+            self.error(&format!("Expected token: {:?}", kind), &token);
         }
     }
 }
+
+// Keywords that always begin a statement, which `parse_statement` consumes
+fn starts_statement(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Let
+            | TokenKind::If
+            | TokenKind::While
+            | TokenKind::For
+            | TokenKind::Function
+            | TokenKind::Class
+            | TokenKind::Import
+            | TokenKind::Return
+    )
+}
+
+fn is_comparison(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::Less | TokenKind::LessEqual | TokenKind::Greater | TokenKind::GreaterEqual
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenizer::tokenize;
+
+    fn parse_source(source: &str) -> Result<ASTNode, Vec<ParserError>> {
+        let tokens = tokenize(source.to_string()).expect("tokenize");
+        parse(&tokens)
+    }
+
+    fn error_messages(source: &str) -> Vec<String> {
+        match parse_source(source) {
+            Ok(ast) => panic!("expected errors, parsed {:?}", ast),
+            Err(errors) => errors.iter().map(ParserError::as_message).collect(),
+        }
+    }
+
+    #[test]
+    fn separate_syntax_errors_are_all_reported() {
+        let source = "let x = ;\nlet y = 2;\nlet z = (1 + );\nlet w = 4;";
+        assert_eq!(
+            error_messages(source),
+            vec![
+                "Unexpected token SemiColon ';' in `let x = ;` at line 1 column 9",
+                "Unexpected token RParen ')' in `( 1 + )` at line 3 column 14",
+            ]
+        );
+    }
+
+    #[test]
+    fn recovery_stops_at_the_next_statement_keyword() {
+        // The first two errors aren't followed by `;`, so recovery has to stop at `if` and `fn`
+        // to find the next one
+        let source = "let x = 1 +\nif (true) { let a = ; }\nfn f() { return ); }";
+        assert_eq!(
+            error_messages(source),
+            vec![
+                "Unexpected token If 'if' in `if` at line 2 column 1",
+                "Unexpected token SemiColon ';' in `let a = ;` at line 2 column 21",
+                "Unexpected token RParen ')' in `) { return )` at line 3 column 17",
+            ]
+        );
+    }
+
+    #[test]
+    fn unclosed_lists_end_at_the_next_statement() {
+        let source = "f(1, 2\nlet a = [1, 2\nreturn 3;";
+        assert_eq!(
+            error_messages(source),
+            vec![
+                "Expected token: RParen at line 2 column 1",
+                "Expected token: RBrack at line 3 column 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_semicolon_keeps_the_next_statement() {
+        let tokens = tokenize("let x = 1\nlet y = 2;".to_string()).expect("tokenize");
+        let mut parser = Parser::new(&tokens);
+        parser.parse_statement();
+        let second = parser.parse_statement();
+        assert_eq!(
+            second,
+            ASTNode::VariableDeclaration {
+                name: "y".to_string(),
+                value: Box::new(ASTNode::NumberLiteral(2.0)),
+            }
+        );
+        assert!(parser.at_end());
+        let errors: Vec<String> = parser.errors.iter().map(ParserError::as_message).collect();
+        assert_eq!(
+            errors,
+            vec!["Expected ';' after variable declaration at line 1 column 9"]
+        );
+    }
+
+    #[test]
+    fn missing_semicolon_is_reported_at_the_end_of_its_statement() {
+        assert_eq!(
+            error_messages("let x = f(1,\n  2)\nreturn x;"),
+            vec!["Expected ';' after variable declaration at line 2 column 4"]
+        );
+    }
+
+    #[test]
+    fn stray_paren_is_reported_at_its_own_position() {
+        assert_eq!(
+            error_messages("let a = 1;\nlet b = a + );"),
+            vec!["Unexpected token RParen ')' in `= a + )` at line 2 column 13"]
+        );
+        assert_eq!(
+            error_messages("\n   )"),
+            vec!["Unexpected token RParen ')' in `)` at line 2 column 4"]
+        );
+    }
+
+    #[test]
+    fn truncated_input_errors_instead_of_hanging() {
+        assert_eq!(
+            error_messages("let x ="),
+            vec!["Unexpected end of input at line 1 column 8"]
+        );
+        assert!(parse_source("").is_ok());
+    }
+
+    #[test]
+    fn unclosed_lists_report_the_end_of_input() {
+        let cases = [
+            ("fn f(a, b", "expected RParen at line 1 column 10"),
+            ("fn f(a,", "expected RParen at line 1 column 8"),
+            ("f(1, 2", "expected RParen at line 1 column 7"),
+            ("let a = [1, 2", "expected RBrack at line 1 column 14"),
+            ("let o = {a: 1", "expected RBrace at line 1 column 14"),
+            ("let [a, b", "expected RBrack at line 1 column 10"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(
+                error_messages(source),
+                vec![format!("Unexpected end of input, {}", expected)],
+                "{}",
+                source
+            );
+        }
+    }
+
+    #[test]
+    fn chained_comparisons_are_rejected() {
+        assert_eq!(
+            error_messages("let ok = 1 < x < 10;"),
+            vec![
+                "Comparisons can't be chained: write `a < b && b < c` instead of `a < b < c` at line 1 column 16"
+            ]
+        );
+    }
+
+    #[test]
+    fn separated_comparisons_are_allowed() {
+        for source in [
+            "let ok = a < b && b < c;",
+            "let ok = a < b == c;",
+            "let ok = (a < b) < c;",
+        ] {
+            assert!(parse_source(source).is_ok(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn duplicate_parameters_are_rejected() {
+        assert_eq!(
+            error_messages("fn f(a, b, a) {}"),
+            vec!["Duplicate parameter 'a' at line 1 column 12"]
+        );
+        assert!(parse_source("fn f(a, b, c) {}").is_ok());
+    }
+
+    #[test]
+    fn parameters_cannot_reuse_the_function_name() {
+        assert_eq!(
+            error_messages("fn f(f) {}"),
+            vec!["Parameter 'f' has the same name as its function at line 1 column 6"]
+        );
+        assert!(parse_source("let g = fn(g) {};").is_ok());
+    }
+
+    #[test]
+    fn trailing_commas_are_allowed() {
+        let cases = [
+            ("let a = [1, 2,];", "let a = [1, 2];"),
+            ("let o = {a: 1, b: 2,};", "let o = {a: 1, b: 2};"),
+            ("f(1, 2,);", "f(1, 2);"),
+            ("fn f(a, b,) {}", "fn f(a, b) {}"),
+        ];
+        for (with_comma, without) in cases {
+            assert_eq!(
+                parse_source(with_comma).unwrap(),
+                parse_source(without).unwrap(),
+                "{}",
+                with_comma
+            );
+        }
+    }
+
+    #[test]
+    fn empty_lists_parse() {
+        for source in ["let a = [];", "let o = {};", "f();", "fn f() {}"] {
+            assert!(parse_source(source).is_ok(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn unknown_loop_labels_are_rejected() {
+        assert_eq!(
+            error_messages("outer: while true { break x; }"),
+            vec!["Unknown loop label 'x' at line 1 column 27"]
+        );
+    }
+
+    #[test]
+    fn labels_do_not_reach_into_nested_functions() {
+        assert_eq!(
+            error_messages("outer: while true { let f = fn() { continue outer; }; }"),
+            vec!["'continue' outside of a loop at line 1 column 36"]
+        );
+    }
+
+    #[test]
+    fn last_statement_of_a_block_or_file_needs_no_semicolon() {
+        for source in [
+            "fn f() { let x = 1; return x }",
+            "fn f() { let x = 1 }",
+            "if true { return }",
+            "let x = 1",
+            "fn f() { return 1; }\nreturn f()",
+        ] {
+            assert!(parse_source(source).is_ok(), "{}", source);
+        }
+    }
+
+    #[test]
+    fn missing_semicolon_inside_a_block_is_an_error() {
+        assert_eq!(
+            error_messages("fn f() {\n  let x = 1\n  return x;\n}"),
+            vec!["Expected ';' after variable declaration at line 2 column 11"]
+        );
+        assert_eq!(
+            error_messages("fn f() { return 1\n let y = 2; }")[0],
+            "Expected ';' after return statement at line 1 column 17"
+        );
+    }
+}
@@ -1,4 +1,6 @@
 use crate::common::TokenizerError;
+use std::iter::Peekable;
+use std::str::Chars;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TokenKind {
@@ -19,6 +21,8 @@ pub enum TokenKind {
     If,
     Else,
     Return,
+    Break,
+    Continue,
     Identifier,
     Let,
     Equal,
@@ -32,20 +36,31 @@ pub enum TokenKind {
     NewLine,
     Comma,
     Dot,
+    Ellipsis,
     Colon,
     Null,
     True,
     False,
     While,
     For,
+    Class,
+    New,
+    Import,
     Mod,
     And,
     Or,
     BitAnd,
     BitOr,
+    Pipe,
     BitXor,
     Inc,
     Dec,
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    ModAssign,
+    NullCoalesce,
     EOF,
 }
 
@@ -79,11 +94,16 @@ fn get_identifier(id: String, line: usize, column: usize) -> Token {
         "if" => Token::new(TokenKind::If, id, line, column),
         "else" => Token::new(TokenKind::Else, id, line, column),
         "return" => Token::new(TokenKind::Return, id, line, column),
+        "break" => Token::new(TokenKind::Break, id, line, column),
+        "continue" => Token::new(TokenKind::Continue, id, line, column),
         "null" => Token::new(TokenKind::Null, id, line, column),
         "true" => Token::new(TokenKind::True, id, line, column),
         "false" => Token::new(TokenKind::False, id, line, column),
         "while" => Token::new(TokenKind::While, id, line, column),
         "for" => Token::new(TokenKind::For, id, line, column),
+        "class" => Token::new(TokenKind::Class, id, line, column),
+        "new" => Token::new(TokenKind::New, id, line, column),
+        "import" => Token::new(TokenKind::Import, id, line, column),
 
         _ => Token::new(TokenKind::Identifier, id, line, column),
     }
@@ -93,18 +113,102 @@ fn error(message: &str, line: usize, column: usize) -> Result<Vec<Token>, Tokeni
     Err(TokenizerError::new(message, line, column))
 }
 
+// Character stream that tracks the line and column of the next character, so every token is
+// positioned where it starts no matter how many characters the tokens before it consumed
+struct SourceChars<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> SourceChars<'a> {
+    fn new(text: &'a str) -> Self {
+        SourceChars {
+            chars: text.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek()
+    }
+    // The character after the next one
+    fn peek_second(&self) -> Option<char> {
+        self.chars.clone().nth(1)
+    }
+    fn starts_with(&self, prefix: &str) -> bool {
+        self.chars.clone().take(prefix.len()).eq(prefix.chars())
+    }
+    fn next(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+}
+
+// Reads an escape sequence starting at the backslash and returns the character it stands for
+fn read_escape(chars: &mut SourceChars) -> Result<char, TokenizerError> {
+    let (line, col) = (chars.line, chars.column);
+    let invalid = |message: String| Err(TokenizerError::new(&message, line, col));
+    chars.next();
+    let Some(n) = chars.next() else {
+        return invalid("Invalid escape character".to_string());
+    };
+    match n {
+        'n' => Ok('\n'),
+        'r' => Ok('\r'),
+        't' => Ok('\t'),
+        '0' => Ok('\0'),
+        '\\' | '"' | '\'' => Ok(n),
+        'u' => {
+            if chars.next() != Some('{') {
+                return invalid("Unicode escape must be written as \\u{...}".to_string());
+            }
+            let mut digits = String::new();
+            loop {
+                match chars.next() {
+                    Some('}') => break,
+                    Some(c) if c.is_ascii_hexdigit() && digits.len() < 6 => digits.push(c),
+                    _ => {
+                        return invalid(
+                            "Unicode escape must be 1 to 6 hex digits inside \\u{...}".to_string(),
+                        )
+                    }
+                }
+            }
+            match u32::from_str_radix(&digits, 16)
+                .ok()
+                .and_then(char::from_u32)
+            {
+                Some(c) => Ok(c),
+                None => invalid(format!("Invalid unicode escape \\u{{{}}}", digits)),
+            }
+        }
+        _ => invalid(format!("Invalid escape character \\{}", n)),
+    }
+}
+
 pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
     let mut tokens = Vec::new();
-    let mut chars = text.chars().peekable();
+    let mut chars = SourceChars::new(&text);
 
-    let mut line: usize = 1;
-    let mut col: usize = 1;
     while let Some(&c) = chars.peek() {
+        let (line, col) = (chars.line, chars.column);
         match c {
             '0'..='9' => {
                 let mut value = String::new();
                 while let Some(&c) = chars.peek() {
-                    if c.is_ascii_digit() || c == '.' {
+                    // A dot only belongs to the number if a digit follows, so `5.abs()` is a
+                    // method call and `1.5.floor()` stops before the second dot
+                    let is_fraction = c == '.'
+                        && !value.contains('.')
+                        && chars.peek_second().is_some_and(|c| c.is_ascii_digit());
+                    if c.is_ascii_digit() || is_fraction {
                         value.push(c);
                         chars.next();
                     } else {
@@ -119,6 +223,14 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                     if c == '+' {
                         tokens.push(Token::new(TokenKind::Inc, "++".to_string(), line, col));
                         chars.next();
+                    } else if c == '=' {
+                        tokens.push(Token::new(
+                            TokenKind::PlusAssign,
+                            "+=".to_string(),
+                            line,
+                            col,
+                        ));
+                        chars.next();
                     } else {
                         tokens.push(Token::new(TokenKind::Plus, "+".to_string(), line, col));
                     }
@@ -132,6 +244,14 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                     if c == '-' {
                         tokens.push(Token::new(TokenKind::Dec, "--".to_string(), line, col));
                         chars.next();
+                    } else if c == '=' {
+                        tokens.push(Token::new(
+                            TokenKind::MinusAssign,
+                            "-=".to_string(),
+                            line,
+                            col,
+                        ));
+                        chars.next();
                     } else {
                         tokens.push(Token::new(TokenKind::Minus, "-".to_string(), line, col));
                     }
@@ -140,16 +260,26 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                 }
             }
             '*' => {
-                tokens.push(Token::new(TokenKind::Star, "*".to_string(), line, col));
                 chars.next();
+                if let Some('=') = chars.peek() {
+                    tokens.push(Token::new(
+                        TokenKind::StarAssign,
+                        "*=".to_string(),
+                        line,
+                        col,
+                    ));
+                    chars.next();
+                } else {
+                    tokens.push(Token::new(TokenKind::Star, "*".to_string(), line, col));
+                }
             }
             '/' => {
                 chars.next();
                 if let Some(&c) = chars.peek() {
                     if c == '/' {
+                        // Line comment, the newline itself is handled by the main loop
                         while let Some(&c) = chars.peek() {
                             if c == '\n' {
-                                line += 1;
                                 break;
                             }
                             chars.next();
@@ -160,23 +290,39 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                         while let Some(&c) = chars.peek() {
                             if c == '/' && last_char == '*' {
                                 break;
-                            } else if c == '\n' {
-                                line += 1;
                             }
                             last_char = c;
                             chars.next();
                         }
+                        chars.next();
+                    } else if c == '=' {
+                        tokens.push(Token::new(
+                            TokenKind::SlashAssign,
+                            "/=".to_string(),
+                            line,
+                            col,
+                        ));
+                        chars.next();
                     } else {
                         tokens.push(Token::new(TokenKind::Slash, "/".to_string(), line, col));
                     }
                 } else {
                     tokens.push(Token::new(TokenKind::Slash, "/".to_string(), line, col));
                 }
-                chars.next();
             }
             '%' => {
-                tokens.push(Token::new(TokenKind::Mod, "%".to_string(), line, col));
                 chars.next();
+                if let Some('=') = chars.peek() {
+                    tokens.push(Token::new(
+                        TokenKind::ModAssign,
+                        "%=".to_string(),
+                        line,
+                        col,
+                    ));
+                    chars.next();
+                } else {
+                    tokens.push(Token::new(TokenKind::Mod, "%".to_string(), line, col));
+                }
             }
             '(' => {
                 tokens.push(Token::new(TokenKind::LParen, "(".to_string(), line, col));
@@ -202,12 +348,7 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                 tokens.push(Token::new(TokenKind::RBrack, "]".to_string(), line, col));
                 chars.next();
             }
-            '\n' => {
-                line += 1;
-                col = 0;
-                chars.next();
-            }
-            ' ' | '\t' | '\r' | '\x0c' => {
+            '\n' | ' ' | '\t' | '\r' | '\x0c' => {
                 chars.next();
             }
             '=' => {
@@ -284,6 +425,24 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                     tokens.push(Token::new(TokenKind::Bang, "!".to_string(), line, col));
                 }
             }
+            // Raw strings keep everything up to the closing quotes, including line breaks and
+            // backslashes
+            '"' if chars.starts_with("\"\"\"") => {
+                for _ in 0..3 {
+                    chars.next();
+                }
+                let mut value = String::new();
+                while !chars.starts_with("\"\"\"") {
+                    match chars.next() {
+                        Some(c) => value.push(c),
+                        None => return error("Unterminated raw string", line, col),
+                    }
+                }
+                for _ in 0..3 {
+                    chars.next();
+                }
+                tokens.push(Token::new(TokenKind::String, value, line, col));
+            }
             '"' | '\'' => {
                 let chr = c;
                 let mut value = String::new();
@@ -293,26 +452,7 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                         break;
                     }
                     if c == '\\' {
-                        chars.next();
-                        let n_0 = chars.peek();
-                        if n_0.is_none() {
-                            return error("Invalid escape character", line, col);
-                        }
-                        let n = n_0.unwrap();
-                        let k = match n {
-                            'n' => '\n',
-                            'r' => '\r',
-                            't' => '\t',
-                            _ => {
-                                return error(
-                                    format!("Invalid escape character \\{}", n).as_str(),
-                                    line,
-                                    col,
-                                )
-                            }
-                        };
-                        value.push(k);
-                        chars.next();
+                        value.push(read_escape(&mut chars)?);
                         continue;
                     }
                     value.push(c);
@@ -327,6 +467,9 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                     if c == '|' {
                         tokens.push(Token::new(TokenKind::Or, "||".to_string(), line, col));
                         chars.next();
+                    } else if c == '>' {
+                        tokens.push(Token::new(TokenKind::Pipe, "|>".to_string(), line, col));
+                        chars.next();
                     } else {
                         return error(format!("Unknown character: '|{}'", c).as_str(), line, col);
                     }
@@ -349,13 +492,42 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                     chars.next();
                 }
             }
+            '?' => {
+                chars.next();
+                if let Some('?') = chars.peek() {
+                    tokens.push(Token::new(
+                        TokenKind::NullCoalesce,
+                        "??".to_string(),
+                        line,
+                        col,
+                    ));
+                    chars.next();
+                } else {
+                    return error("Unknown character: '?'", line, col);
+                }
+            }
             '^' => {
                 chars.next();
                 tokens.push(Token::new(TokenKind::BitXor, "^".to_string(), line, col))
             }
             '.' => {
                 chars.next();
-                tokens.push(Token::new(TokenKind::Dot, ".".to_string(), line, col));
+                if let Some('.') = chars.peek() {
+                    chars.next();
+                    if let Some('.') = chars.peek() {
+                        chars.next();
+                        tokens.push(Token::new(
+                            TokenKind::Ellipsis,
+                            "...".to_string(),
+                            line,
+                            col,
+                        ));
+                    } else {
+                        return error("Unknown character: '..'", line, col);
+                    }
+                } else {
+                    tokens.push(Token::new(TokenKind::Dot, ".".to_string(), line, col));
+                }
             }
 
             _ => {
@@ -376,9 +548,113 @@ pub fn tokenize(text: String) -> Result<Vec<Token>, TokenizerError> {
                 }
             }
         }
-        col += 1;
     }
 
-    tokens.push(Token::new(TokenKind::EOF, "".to_string(), line, col));
+    tokens.push(Token::new(
+        TokenKind::EOF,
+        "".to_string(),
+        chars.line,
+        chars.column,
+    ));
     Ok(tokens)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The value of the single string literal in `source`
+    fn string_value(source: &str) -> String {
+        let tokens = tokenize(source.to_string()).expect("tokenize");
+        assert_eq!(tokens[0].kind, TokenKind::String, "{}", source);
+        tokens[0].value.clone()
+    }
+
+    fn error(source: &str) -> String {
+        match tokenize(source.to_string()) {
+            Ok(tokens) => panic!("expected an error, got {:?}", tokens),
+            Err(e) => e.as_message(),
+        }
+    }
+
+    #[test]
+    fn escapes_stand_for_their_characters() {
+        let cases = [
+            (r#""a\\b""#, "a\\b"),
+            (r#""say \"hi\"""#, "say \"hi\""),
+            (r#"'it\'s'"#, "it's"),
+            (r#""\n\r\t""#, "\n\r\t"),
+            (r#""nul\0""#, "nul\0"),
+            (r#""\u{41}""#, "A"),
+            (r#""\u{e9}""#, "é"),
+            (r#""\u{1F600}""#, "😀"),
+            (r#""\u{10FFFF}""#, "\u{10FFFF}"),
+        ];
+        for (source, expected) in cases {
+            assert_eq!(string_value(source), expected, "{}", source);
+        }
+    }
+
+    #[test]
+    fn invalid_unicode_escapes_are_errors() {
+        assert_eq!(
+            error(r#""\u{D800}""#),
+            "Invalid unicode escape \\u{D800} at line 1 column 2"
+        );
+        assert_eq!(
+            error(r#""\u{110000}""#),
+            "Invalid unicode escape \\u{110000} at line 1 column 2"
+        );
+        assert_eq!(
+            error(r#""\u{1234567}""#),
+            "Unicode escape must be 1 to 6 hex digits inside \\u{...} at line 1 column 2"
+        );
+        assert_eq!(
+            error(r#""\u{}""#),
+            "Invalid unicode escape \\u{} at line 1 column 2"
+        );
+        assert_eq!(
+            error(r#""\u41""#),
+            "Unicode escape must be written as \\u{...} at line 1 column 2"
+        );
+        assert_eq!(
+            error(r#""\u{41""#),
+            "Unicode escape must be 1 to 6 hex digits inside \\u{...} at line 1 column 2"
+        );
+    }
+
+    #[test]
+    fn unknown_escapes_are_errors() {
+        assert_eq!(
+            error(r#""\q""#),
+            "Invalid escape character \\q at line 1 column 2"
+        );
+    }
+
+    #[test]
+    fn raw_strings_keep_their_contents() {
+        assert_eq!(
+            string_value("\"\"\"line one\n  line \"two\"\n\"\"\""),
+            "line one\n  line \"two\"\n"
+        );
+        assert_eq!(
+            string_value(r#""""C:\path\n{"a": 1}""""#),
+            r#"C:\path\n{"a": 1}"#
+        );
+        assert_eq!(string_value(r#""""""""#), "");
+    }
+
+    #[test]
+    fn raw_strings_track_lines() {
+        let tokens = tokenize("\"\"\"a\nb\"\"\" x".to_string()).expect("tokenize");
+        assert_eq!((tokens[1].value.as_str(), tokens[1].line), ("x", 2));
+    }
+
+    #[test]
+    fn unterminated_raw_strings_are_errors() {
+        assert_eq!(
+            error("let s = \"\"\"never\nclosed\"\";"),
+            "Unterminated raw string at line 1 column 9"
+        );
+    }
+}
@@ -1,9 +1,23 @@
 use crate::ast::ASTNode;
-use crate::tokenizer::TokenKind;
-use crate::treewalk::stdlib::{array_methods, number_methods, object_methods, string_methods};
-use crate::treewalk::value::{Scope, Value};
+use crate::common::Warning;
+use crate::errors::EvalError;
+use crate::parser;
+use crate::tokenizer::{self, TokenKind};
+use crate::treewalk::stdlib::{
+    array_arities, array_evaluator_arities, array_evaluator_methods, array_methods,
+    boolean_arities, boolean_methods, map_arities, map_key, map_methods, math_arities,
+    math_constants, math_methods, number_arities, number_methods, object_arities, object_methods,
+    set_arities, set_methods, std_arities, string_arities, string_methods, universal_arities,
+    universal_methods, Arity, EvaluatorMethod,
+};
+use crate::treewalk::value::{
+    resolve_index, Class, Freezable, MapKey, ObjectMap, OrderedMap, Scope, Value,
+};
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use super::stdlib::std_methods;
@@ -13,17 +27,73 @@ pub fn evaluate(program: ASTNode) -> Value {
         ASTNode::Program(statements) => statements,
         _ => {
             runtime_error("Program node expected");
-            return Value::Null;
         }
     });
     evaluator.evaluate_program()
 }
 
-pub fn runtime_error(msg: &str) -> Value {
+pub fn runtime_error(msg: &str) -> ! {
     panic!("Runtime error: {}", msg);
 }
 
+// Array methods that look for an argument using `deep_eq`
+const SEARCH_METHODS: [&str; 5] = ["find", "index_of", "last_index_of", "contains", "count"];
+
 type MethodMap = HashMap<String, fn(&Value, Vec<Value>) -> Value>;
+type EvaluatorMethodMap = HashMap<String, EvaluatorMethod>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceKind {
+    Statement,
+    Call {
+        callee: String,
+        arguments: Vec<Value>,
+    },
+    Return {
+        callee: String,
+        value: Value,
+    },
+    Assignment {
+        name: String,
+        value: Value,
+    },
+}
+
+/// Reported to the tracer installed with `TreeWalk::set_tracer` as the program runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEvent {
+    pub kind: TraceKind,
+    pub node: &'static str,
+    // AST nodes don't carry spans yet, so this is always None for now
+    pub position: Option<(usize, usize)>,
+    pub depth: usize,
+}
+
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[depth {}] ", self.depth)?;
+        match &self.kind {
+            TraceKind::Statement => write!(f, "{}", self.node),
+            TraceKind::Call { callee, arguments } => {
+                let arguments: Vec<String> = arguments.iter().map(|a| a.to_string()).collect();
+                write!(f, "call {}({})", callee, arguments.join(", "))
+            }
+            TraceKind::Return { callee, value } => write!(f, "return {} -> {}", callee, value),
+            TraceKind::Assignment { name, value } => write!(f, "assign {} = {}", name, value),
+        }
+    }
+}
+
+pub type Tracer = Box<dyn FnMut(&TraceEvent)>;
+
+/// An assignable location, resolved once so the expressions leading to it aren't re-evaluated
+/// when it is both read and written (as in `+=` and `++`).
+enum Place {
+    Variable(String),
+    Property(Rc<RefCell<Freezable<ObjectMap>>>, String),
+    Element(Rc<RefCell<Freezable<Vec<Value>>>>, usize),
+    Entry(Rc<RefCell<Freezable<OrderedMap<MapKey>>>>, MapKey),
+}
 
 pub struct TreeWalk {
     program: Vec<ASTNode>,
@@ -34,6 +104,28 @@ pub struct TreeWalk {
     number_methods: MethodMap,
     array_methods: MethodMap,
     object_methods: MethodMap,
+    map_methods: MethodMap,
+    set_methods: MethodMap,
+    boolean_methods: MethodMap,
+    universal_methods: MethodMap,
+    array_evaluator_methods: EvaluatorMethodMap,
+    // Keyed by qualified name: "std.print", "Array.push", or "universal.type"
+    arities: HashMap<String, Arity>,
+
+    warnings_enabled: bool,
+    warnings: Vec<Warning>,
+    // Line and column of the operator or call being evaluated, attached to warnings
+    position: Option<(usize, usize)>,
+    tracer: Option<Tracer>,
+
+    // The file being run, if it came from one. Imports in it resolve against its directory
+    script_path: Option<PathBuf>,
+    // Files whose import is in progress, innermost last, to detect cycles
+    import_stack: Vec<PathBuf>,
+    // Every file imported without `as` so far, so each one only runs once
+    imported: HashSet<PathBuf>,
+    // The object made for each file imported with `as`
+    modules: HashMap<PathBuf, Value>,
 }
 
 impl TreeWalk {
@@ -48,37 +140,178 @@ impl TreeWalk {
             number_methods: HashMap::new(),
             array_methods: HashMap::new(),
             object_methods: HashMap::new(),
+            map_methods: HashMap::new(),
+            set_methods: HashMap::new(),
+            boolean_methods: HashMap::new(),
+            universal_methods: HashMap::new(),
+            array_evaluator_methods: HashMap::new(),
+            arities: HashMap::new(),
+
+            warnings_enabled: false,
+            warnings: Vec::new(),
+            position: None,
+            tracer: None,
+
+            script_path: None,
+            import_stack: Vec::new(),
+            imported: HashSet::new(),
+            modules: HashMap::new(),
+        }
+    }
+
+    /// Sets the path of the file being run, so its relative imports are found next to it rather
+    /// than in the working directory.
+    pub fn with_script_path(mut self, path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        self.script_path = Some(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()));
+        self
+    }
+
+    pub fn with_warnings(mut self, enabled: bool) -> Self {
+        self.warnings_enabled = enabled;
+        self
+    }
+
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Looks up a top-level binding, e.g. to call a function after the program has run.
+    pub fn global(&self, name: &str) -> Option<Value> {
+        self.global_environment.borrow().get(name)
+    }
+
+    /// Returns to the global scope. Runtime errors unwind without restoring the caller's scope, so
+    /// call this before reusing the evaluator after catching one.
+    pub fn reset_scope(&mut self) {
+        self.current_scope = self.global_environment.clone();
+        self.import_stack.clear();
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn warn(&mut self, message: &str) {
+        if self.warnings_enabled {
+            self.warnings.push(Warning::new(message, self.position));
         }
     }
 
+    pub fn set_tracer(&mut self, tracer: Tracer) {
+        self.tracer = Some(tracer);
+    }
+
+    // The event is only built when a tracer is installed, so tracing costs nothing otherwise
+    fn trace(&mut self, node: &'static str, kind: impl FnOnce() -> TraceKind) {
+        if let Some(tracer) = self.tracer.as_mut() {
+            tracer(&TraceEvent {
+                kind: kind(),
+                node,
+                position: None,
+                depth: self.current_scope.borrow().depth(),
+            });
+        }
+    }
+
+    /// Runs a program in the global scope. This is so even if an earlier run ended in a caught
+    /// runtime error inside a function, so top-level declarations are never bound in a leftover
+    /// function scope.
     pub fn evaluate(&mut self, program: ASTNode) -> Value {
         self.program = match program {
             ASTNode::Program(statements) => statements,
             _ => {
                 runtime_error("Program node expected");
-                return Value::Null;
             }
         };
+        self.reset_scope();
         self.evaluate_program()
     }
 
+    /// Like `evaluate`, but returns runtime errors and `std.exit` as an `EvalError` instead of
+    /// unwinding into the caller. Runtime errors are still reported by the panic hook.
+    pub fn try_evaluate(&mut self, program: ASTNode) -> Result<Value, EvalError> {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| self.evaluate(program)));
+        result.map_err(|payload| {
+            self.reset_scope();
+            match payload.downcast::<EvalError>() {
+                Ok(error) => *error,
+                Err(payload) => match payload.downcast::<String>() {
+                    Ok(message) => match message.strip_prefix("Runtime error: ") {
+                        Some(message) => EvalError::Runtime(message.to_string()),
+                        None => EvalError::Runtime(*message),
+                    },
+                    Err(payload) => panic::resume_unwind(payload),
+                },
+            }
+        })
+    }
+
     fn evaluate_program(&mut self) -> Value {
         self.string_methods = string_methods();
         self.number_methods = number_methods();
         self.array_methods = array_methods();
         self.object_methods = object_methods();
+        self.map_methods = map_methods();
+        self.set_methods = set_methods();
+        self.boolean_methods = boolean_methods();
+        self.universal_methods = universal_methods();
+        self.array_evaluator_methods = array_evaluator_methods();
 
-        let mut std_map = HashMap::new();
+        let arity_tables = [
+            ("std", std_arities()),
+            ("std.math", math_arities()),
+            ("String", string_arities()),
+            ("Number", number_arities()),
+            ("Array", array_arities()),
+            ("Array", array_evaluator_arities()),
+            ("Object", object_arities()),
+            ("Map", map_arities()),
+            ("Set", set_arities()),
+            ("Boolean", boolean_arities()),
+            ("universal", universal_arities()),
+        ];
+        for (prefix, table) in arity_tables {
+            for (name, arity) in table {
+                self.arities.insert(format!("{}.{}", prefix, name), arity);
+            }
+        }
+
+        let mut std_map = ObjectMap::new();
         for method in std_methods() {
-            std_map.insert(method.0.to_string(), Value::RustFunction(method.1));
+            std_map.insert(
+                method.0.to_string(),
+                Value::RustFunction(format!("std.{}", method.0), method.1),
+            );
+        }
+        let mut math_map = ObjectMap::new();
+        for method in math_methods() {
+            math_map.insert(
+                method.0.to_string(),
+                Value::RustFunction(format!("std.math.{}", method.0), method.1),
+            );
         }
+        for (name, constant) in math_constants() {
+            math_map.insert(name, Value::Number(constant));
+        }
+        std_map.insert(
+            "math".to_string(),
+            Value::Object(Rc::new(RefCell::new(math_map.into()))),
+        );
         self.global_environment.borrow_mut().insert(
             "std".to_string(),
-            Value::Object(Rc::new(RefCell::new(std_map))),
+            Value::Object(Rc::new(RefCell::new(std_map.into()))),
         );
 
+        let program = self.program.clone();
+        self.hoist_functions(&program);
         let mut result = Value::Null;
-        for stmt in self.program.clone() {
+        for stmt in program {
+            if is_hoisted(&stmt) {
+                result = Value::Null;
+                continue;
+            }
+            self.trace(stmt.kind_name(), || TraceKind::Statement);
             result = self.evaluate_node(&stmt);
             if let Value::Return(val) = result {
                 return *val;
@@ -87,17 +320,26 @@ impl TreeWalk {
         result
     }
 
+    // Binds the named functions declared directly in `statements` before any statement runs, so
+    // a function can be called above its declaration and two functions can call each other
+    fn hoist_functions(&mut self, statements: &[ASTNode]) {
+        for stmt in statements.iter().filter(|stmt| is_hoisted(stmt)) {
+            self.trace(stmt.kind_name(), || TraceKind::Statement);
+            self.evaluate_node(stmt);
+        }
+    }
+
     fn evaluate_node(&mut self, node: &ASTNode) -> Value {
         match node {
             ASTNode::NumberLiteral(n) => Value::Number(*n),
             ASTNode::BooleanLiteral(b) => Value::Boolean(*b),
             ASTNode::NullLiteral => Value::Null,
             ASTNode::ObjectLiteral(properties) => {
-                let mut obj = HashMap::new();
+                let mut obj = ObjectMap::new();
                 for (key, val) in properties {
                     obj.insert(key.clone(), self.evaluate_node(val));
                 }
-                Value::Object(Rc::new(RefCell::new(obj)))
+                Value::Object(Rc::new(RefCell::new(obj.into())))
             }
             ASTNode::StringLiteral(s) => Value::String(s.clone()),
             ASTNode::ArrayLiteral(values) => {
@@ -105,7 +347,7 @@ impl TreeWalk {
                 for val in values {
                     arr.push(self.evaluate_node(val));
                 }
-                Value::Array(Rc::new(RefCell::new(arr)))
+                Value::Array(Rc::new(RefCell::new(arr.into())))
             }
             ASTNode::Variable(name) => self
                 .current_scope
@@ -114,35 +356,106 @@ impl TreeWalk {
                 .unwrap_or_else(|| runtime_error(&format!("Undefined variable: {}", name))),
             ASTNode::VariableDeclaration { name, value } => {
                 let val = self.evaluate_node(value);
+                self.trace("VariableDeclaration", || TraceKind::Assignment {
+                    name: name.clone(),
+                    value: val.clone(),
+                });
                 self.current_scope.borrow_mut().insert(name.clone(), val);
                 Value::Null
             }
+            ASTNode::ArrayDestructuring { names, rest, value } => {
+                let val = self.evaluate_node(value);
+                let Value::Array(array) = &val else {
+                    runtime_error(&format!(
+                        "Cannot destructure {} as an array",
+                        val.type_name()
+                    ));
+                };
+                let items = array.borrow().to_vec();
+                let fits = if rest.is_some() {
+                    items.len() >= names.len()
+                } else {
+                    items.len() == names.len()
+                };
+                if !fits {
+                    runtime_error(&format!(
+                        "Cannot destructure array of length {} into {} names",
+                        items.len(),
+                        names.len()
+                    ));
+                }
+                let mut bindings: Vec<(String, Value)> =
+                    names.iter().cloned().zip(items.iter().cloned()).collect();
+                if let Some(rest) = rest {
+                    let remainder = items[names.len()..].to_vec();
+                    bindings.push((
+                        rest.clone(),
+                        Value::Array(Rc::new(RefCell::new(remainder.into()))),
+                    ));
+                }
+                for (name, item) in bindings {
+                    self.trace("ArrayDestructuring", || TraceKind::Assignment {
+                        name: name.clone(),
+                        value: item.clone(),
+                    });
+                    self.current_scope.borrow_mut().insert(name, item);
+                }
+                Value::Null
+            }
             ASTNode::Expression(expr) => self.evaluate_node(expr),
-            ASTNode::BinaryOp { left, op, right } => self.evaluate_binary_op(op, left, right),
+            ASTNode::BinaryOp {
+                left,
+                op,
+                right,
+                position,
+            } => self.evaluate_binary_op(op, left, right, *position),
             ASTNode::UnaryOp { op, operand } => self.evaluate_unary_op(op, operand),
+            ASTNode::PostfixOp { op, operand } => self.evaluate_increment(op, operand, true),
             ASTNode::MemberAccess { object, member } => {
                 let obj_val = self.evaluate_node(object);
-                let obj_val_2 = obj_val.clone();
-                if let Value::Object(properties) = obj_val {
-                    let properties = properties.borrow();
-                    match properties.get(member) {
-                        Some(val) => val.clone(),
-                        None => {
-                            if let Some(_method) = self.object_methods.get(member) {
-                                return Value::Method {
-                                    receiver: Box::new(obj_val_2.clone()),
-                                    method_name: member.clone(),
-                                };
-                            }
-                            runtime_error(&format!("Property '{}' not found", member));
-                            Value::Null
+                self.member_value(obj_val, member)
+            }
+            ASTNode::Index { object, index } => {
+                let obj_val = self.evaluate_node(object);
+                let index_val = self.evaluate_node(index);
+                match (&obj_val, &index_val) {
+                    (Value::Array(values), Value::Number(i)) => {
+                        let values = values.borrow();
+                        match resolve_index(*i, values.len()) {
+                            Some(i) => values[i].clone(),
+                            None => runtime_error(&format!(
+                                "Index out of bounds: index {}, length {}",
+                                i,
+                                values.len()
+                            )),
                         }
                     }
-                } else {
-                    Value::Method {
-                        receiver: Box::new(obj_val),
-                        method_name: member.clone(),
+                    (Value::String(s), Value::Number(i)) => {
+                        let length = s.chars().count();
+                        match resolve_index(*i, length) {
+                            Some(i) => Value::String(s.chars().nth(i).unwrap().to_string()),
+                            None => runtime_error(&format!(
+                                "Index out of bounds: index {}, length {}",
+                                i, length
+                            )),
+                        }
+                    }
+                    (Value::Map(entries), key) => entries
+                        .borrow()
+                        .get(&map_key("Map index", key))
+                        .cloned()
+                        .unwrap_or(Value::Null),
+                    (Value::Object(properties), Value::String(key)) => {
+                        match properties.borrow().get(key) {
+                            Some(val) => val.clone(),
+                            None => runtime_error(&format!("Property '{}' not found", key)),
+                        }
                     }
+                    _ => runtime_error(&format!(
+                        "Cannot index {} with {}",
+                        obj_val.type_name(),
+                        index_val.type_name()
+                    )),
                 }
             }
             ASTNode::Block(statements) => {
@@ -150,10 +463,19 @@ impl TreeWalk {
                 self.current_scope =
                     Rc::new(RefCell::new(Scope::new(Some(previous_scope.clone()))));
 
+                self.hoist_functions(statements);
                 let mut result = Value::Null;
                 for stmt in statements {
+                    if is_hoisted(stmt) {
+                        result = Value::Null;
+                        continue;
+                    }
+                    self.trace(stmt.kind_name(), || TraceKind::Statement);
                     result = self.evaluate_node(stmt);
-                    if let Value::Return(_) = result {
+                    if matches!(
+                        result,
+                        Value::Return(_) | Value::Break(_) | Value::Continue(_)
+                    ) {
                         break;
                     }
                 }
@@ -199,130 +521,514 @@ impl TreeWalk {
                     func
                 }
             }
-            ASTNode::WhileStatement { condition, body } => {
+            ASTNode::WhileStatement {
+                label,
+                condition,
+                body,
+                alternative,
+            } => {
                 let mut result = Value::Null;
                 while self.evaluate_node(condition).is_truthy() {
                     result = self.evaluate_node(body);
-                    if let Value::Return(_) = result {
-                        break;
+                    match result {
+                        Value::Break(ref target) if targets_loop(target, label) => {
+                            return Value::Null
+                        }
+                        Value::Continue(ref target) if targets_loop(target, label) => {
+                            result = Value::Null
+                        }
+                        // Returns, and signals for an outer loop, leave this loop as they are
+                        Value::Return(_) | Value::Break(_) | Value::Continue(_) => return result,
+                        _ => {}
                     }
                 }
-                result
+                match alternative {
+                    Some(alternative) => self.evaluate_node(alternative),
+                    None => result,
+                }
             }
             ASTNode::ForStatement {
+                label,
                 start,
                 condition,
                 iter,
                 body,
+                alternative,
             } => {
                 let mut result = Value::Null;
                 self.evaluate_node(start);
                 while self.evaluate_node(condition).is_truthy() {
                     result = self.evaluate_node(body);
-                    if let Value::Return(_) = result {
-                        break;
+                    match result {
+                        Value::Break(ref target) if targets_loop(target, label) => {
+                            return Value::Null
+                        }
+                        Value::Continue(ref target) if targets_loop(target, label) => {
+                            result = Value::Null
+                        }
+                        // Returns, and signals for an outer loop, leave this loop as they are
+                        Value::Return(_) | Value::Break(_) | Value::Continue(_) => return result,
+                        _ => {}
                     }
                     self.evaluate_node(iter);
                 }
-                result
+                match alternative {
+                    Some(alternative) => self.evaluate_node(alternative),
+                    None => result,
+                }
+            }
+            ASTNode::FunctionCall {
+                callee,
+                arguments,
+                position,
+            } => {
+                // A function stored on an object and called through it gets the object as `this`
+                let (func, this) = match callee.as_ref() {
+                    ASTNode::MemberAccess { object, member } => {
+                        let obj_val = self.evaluate_node(object);
+                        let func = self.member_value(obj_val.clone(), member);
+                        let this = matches!(
+                            (&obj_val, &func),
+                            (Value::Object(_), Value::Function { .. })
+                        )
+                        .then_some(obj_val);
+                        (func, this)
+                    }
+                    _ => (self.evaluate_node(callee), None),
+                };
+                let args: Vec<Value> = arguments
+                    .iter()
+                    .map(|arg| self.evaluate_node(arg))
+                    .collect();
+                self.position = Some(*position);
+                self.call_named_function(&callee_name(callee), &func, this, args)
             }
-            ASTNode::FunctionCall { callee, arguments } => {
-                let func = self.evaluate_node(callee);
-
-                match func {
-                    Value::Function {
-                        parameters,
-                        body,
-                        env,
-                    } => {
-                        if parameters.len() != arguments.len() {
-                            runtime_error("Argument count mismatch");
-                        }
-
-                        let new_scope = Rc::new(RefCell::new(Scope::new(Some(env.clone()))));
-                        {
-                            let mut scope_borrow = new_scope.borrow_mut();
-                            for (param, arg) in parameters.iter().zip(arguments) {
-                                let arg_val = self.evaluate_node(arg);
-                                scope_borrow.insert(param.clone(), arg_val);
-                            }
-                        }
-
-                        let previous_scope = self.current_scope.clone();
-                        self.current_scope = new_scope;
-
-                        let result = self.evaluate_node(&body);
 
-                        self.current_scope = previous_scope;
-                        if let Value::Return(val) = result {
-                            *val
-                        } else {
-                            Value::Null
-                        }
+            ASTNode::ClassDeclaration { name, methods } => {
+                let methods: ObjectMap = methods
+                    .iter()
+                    .filter_map(|method| match method {
+                        ASTNode::FunctionDeclaration {
+                            name: Some(name),
+                            parameters,
+                            body,
+                        } => Some((
+                            name.clone(),
+                            Value::Function {
+                                parameters: parameters.clone(),
+                                body: body.clone(),
+                                env: self.current_scope.clone(),
+                            },
+                        )),
+                        _ => None,
+                    })
+                    .collect();
+                let class = Value::Class(Rc::new(Class {
+                    name: name.clone(),
+                    methods,
+                }));
+                self.current_scope.borrow_mut().insert(name.clone(), class);
+                Value::Null
+            }
+            ASTNode::New { class, arguments } => {
+                let class = match self.evaluate_node(class) {
+                    Value::Class(class) => class,
+                    other => {
+                        runtime_error(&format!("`new` expects a class: got {}", other.type_name()))
+                    }
+                };
+                let args: Vec<Value> = arguments
+                    .iter()
+                    .map(|arg| self.evaluate_node(arg))
+                    .collect();
+                self.position = None;
+                self.instantiate(&class, args)
+            }
+            ASTNode::Import { path, alias } => {
+                let resolved = self.resolve_import(path);
+                match alias {
+                    Some(alias) => {
+                        let module = self.import_module(path, resolved);
+                        self.current_scope
+                            .borrow_mut()
+                            .insert(alias.clone(), module);
                     }
-                    Value::Method {
-                        receiver,
-                        method_name,
-                    } => self.call_method(
-                        *receiver,
-                        &method_name,
-                        &arguments
-                            .iter()
-                            .map(|arg| Box::new(arg.clone()))
-                            .collect::<Vec<_>>(),
-                    ),
-                    Value::RustFunction(func) => {
-                        let args: Vec<Value> = arguments
-                            .iter()
-                            .map(|arg| self.evaluate_node(arg))
-                            .collect();
-                        func(&Value::Null, args)
+                    None => {
+                        if self.imported.insert(resolved.clone()) {
+                            self.run_import(path, resolved);
+                        }
                     }
-                    _ => runtime_error("Called value is not a function"),
                 }
+                Value::Null
             }
-
             ASTNode::ReturnStatement(expr) => {
                 let val = self.evaluate_node(expr);
                 Value::Return(Box::new(val))
             }
+            ASTNode::Break(label) => Value::Break(label.clone()),
+            ASTNode::Continue(label) => Value::Continue(label.clone()),
             _ => runtime_error(format!("Unsupported AST node: {:?}", node).as_str()),
         }
     }
-    fn call_method(
-        &mut self,
-        receiver: Value,
-        method_name: &str,
-        arg_nodes: &[Box<ASTNode>],
-    ) -> Value {
-        let args: Vec<Value> = arg_nodes
+    // Reads `object.member`: a property of an object, or else a method of the value's type
+    fn member_value(&self, obj_val: Value, member: &str) -> Value {
+        if let Value::Object(properties) = &obj_val {
+            if let Some(val) = properties.borrow().get(member) {
+                return val.clone();
+            }
+            if !self.object_methods.contains_key(member)
+                && !self.universal_methods.contains_key(member)
+            {
+                runtime_error(&format!("Property '{}' not found", member));
+            }
+        }
+        Value::Method {
+            receiver: Box::new(obj_val),
+            method_name: member.to_string(),
+        }
+    }
+
+    // Finds the file an import refers to. One that is still being imported further up the chain
+    // is a cycle.
+    fn resolve_import(&self, path: &str) -> PathBuf {
+        let base = self.import_stack.last().or(self.script_path.as_ref());
+        let resolved = match base.and_then(|file| file.parent()) {
+            Some(directory) => directory.join(path),
+            None => PathBuf::from(path),
+        };
+        let resolved = resolved
+            .canonicalize()
+            .unwrap_or_else(|e| runtime_error(&format!("Cannot import '{}': {}", path, e)));
+        if self.import_stack.contains(&resolved) || self.script_path.as_ref() == Some(&resolved) {
+            runtime_error(&format!(
+                "Cyclic import: '{}' is already being imported",
+                path
+            ));
+        }
+        resolved
+    }
+
+    // Runs the file in a scope of its own and returns an object holding its top-level bindings.
+    // The object is reused when the same file is imported with `as` again.
+    fn import_module(&mut self, path: &str, resolved: PathBuf) -> Value {
+        if let Some(module) = self.modules.get(&resolved) {
+            return module.clone();
+        }
+        let previous_scope = self.current_scope.clone();
+        self.current_scope = Rc::new(RefCell::new(Scope::new(Some(
+            self.global_environment.clone(),
+        ))));
+        self.run_import(path, resolved.clone());
+        let properties: ObjectMap = self.current_scope.borrow().bindings().into_iter().collect();
+        self.current_scope = previous_scope;
+        let module = Value::Object(Rc::new(RefCell::new(properties.into())));
+        self.modules.insert(resolved, module.clone());
+        module
+    }
+
+    // Runs another file's statements in the current scope, so its declarations are bound there
+    fn run_import(&mut self, path: &str, resolved: PathBuf) {
+        let contents = std::fs::read_to_string(&resolved)
+            .unwrap_or_else(|e| runtime_error(&format!("Cannot import '{}': {}", path, e)));
+        let tokens = tokenizer::tokenize(contents.clone()).unwrap_or_else(|e| {
+            runtime_error(&format!(
+                "Cannot import '{}': {}",
+                path,
+                e.as_detailed_message(&contents)
+            ))
+        });
+        let statements = match parser::parse(&tokens) {
+            Ok(ASTNode::Program(statements)) => statements,
+            Ok(_) => Vec::new(),
+            Err(errors) => {
+                let details: Vec<String> = errors
+                    .iter()
+                    .map(|error| error.as_detailed_message(&contents))
+                    .collect();
+                runtime_error(&format!(
+                    "Cannot import '{}':\n{}",
+                    path,
+                    details.join("\n")
+                ))
+            }
+        };
+
+        self.import_stack.push(resolved);
+        self.hoist_functions(&statements);
+        for stmt in &statements {
+            if is_hoisted(stmt) {
+                continue;
+            }
+            self.trace(stmt.kind_name(), || TraceKind::Statement);
+            // A top-level `return` ends the imported file early
+            if let Value::Return(_) = self.evaluate_node(stmt) {
+                break;
+            }
+        }
+        self.import_stack.pop();
+    }
+
+    // Builds an object holding every method of the class but `constructor`, then runs the
+    // constructor on it
+    fn instantiate(&mut self, class: &Class, args: Vec<Value>) -> Value {
+        let properties: ObjectMap = class
+            .methods
             .iter()
-            .map(|arg| self.evaluate_node(arg))
+            .filter(|(name, _)| name.as_str() != "constructor")
+            .map(|(name, method)| (name.clone(), method.clone()))
             .collect();
-        let method = match &receiver {
-            Value::String(_) => self.string_methods.get(method_name),
-            Value::Number(_) => self.number_methods.get(method_name),
-            Value::Array(_) => self.array_methods.get(method_name),
-            Value::Object(_) => self.object_methods.get(method_name),
-            _ => None,
+        let instance = Value::Object(Rc::new(RefCell::new(properties.into())));
+        match class.methods.get("constructor") {
+            Some(constructor) => {
+                let name = format!("{}.constructor", class.name);
+                self.call_named_function(&name, constructor, Some(instance.clone()), args);
+            }
+            None if !args.is_empty() => {
+                let arity = Arity {
+                    min: 0,
+                    max: Some(0),
+                };
+                self.arity_error("Class", &class.name, arity, args.len());
+            }
+            None => {}
+        }
+        instance
+    }
+
+    /// Calls any callable value with already evaluated arguments. Native methods that take a
+    /// callback use this to invoke it.
+    pub fn call_function(&mut self, func: &Value, args: Vec<Value>) -> Value {
+        self.call_named_function("<anonymous>", func, None, args)
+    }
+
+    // `name` is how the callee was referred to at the call site, for error messages. `this` is
+    // the object the function was read from, if it was called as `object.name(...)`
+    fn call_named_function(
+        &mut self,
+        name: &str,
+        func: &Value,
+        this: Option<Value>,
+        args: Vec<Value>,
+    ) -> Value {
+        self.trace("FunctionCall", || TraceKind::Call {
+            callee: name.to_string(),
+            arguments: args.clone(),
+        });
+        let result = self.dispatch_call(name, func, this, args);
+        self.trace("FunctionCall", || TraceKind::Return {
+            callee: name.to_string(),
+            value: result.clone(),
+        });
+        result
+    }
+
+    fn dispatch_call(
+        &mut self,
+        name: &str,
+        func: &Value,
+        this: Option<Value>,
+        args: Vec<Value>,
+    ) -> Value {
+        match func {
+            Value::Function {
+                parameters,
+                body,
+                env,
+            } => {
+                if parameters.len() != args.len() {
+                    let arity = Arity {
+                        min: parameters.len(),
+                        max: Some(parameters.len()),
+                    };
+                    self.arity_error("Function", name, arity, args.len());
+                }
+
+                let new_scope = Rc::new(RefCell::new(Scope::new(Some(env.clone()))));
+                {
+                    let mut scope_borrow = new_scope.borrow_mut();
+                    if let Some(this) = this {
+                        scope_borrow.insert("this".to_string(), this);
+                    }
+                    for (param, arg_val) in parameters.iter().zip(args) {
+                        scope_borrow.insert(param.clone(), arg_val);
+                    }
+                }
+
+                let previous_scope = self.current_scope.clone();
+                self.current_scope = new_scope;
+
+                let result = self.evaluate_node(body);
+
+                self.current_scope = previous_scope;
+                if let Value::Return(val) = result {
+                    *val
+                } else {
+                    Value::Null
+                }
+            }
+            Value::Method {
+                receiver,
+                method_name,
+            } => self.call_method(*receiver.clone(), method_name, args),
+            Value::RustFunction(name, func) => {
+                self.check_arity("Function", name, name, args.len());
+                func(&Value::Null, args)
+            }
+            Value::Class(class) => runtime_error(&format!(
+                "Class '{0}' must be instantiated with `new {0}(...)`",
+                class.name
+            )),
+            _ => {
+                self.warn(&format!(
+                    "Calling '{}', which is {}",
+                    name,
+                    func.type_name()
+                ));
+                runtime_error(&format!(
+                    "Called value is not a function: got {}",
+                    func.type_name()
+                ))
+            }
+        }
+    }
+
+    /// Methods are looked up in the universal methods first, then in the methods that need the
+    /// evaluator (see `evaluator_type_methods`), then in the plain per-type method maps. A
+    /// per-type method with the same name as a universal one takes precedence, so a type can
+    /// extend e.g. `to_string` with arguments of its own.
+    fn call_method(&mut self, receiver: Value, method_name: &str, args: Vec<Value>) -> Value {
+        let qualified_name = format!("{}.{}", receiver.type_name(), method_name);
+        let type_methods = self.type_methods(&receiver);
+        let overridden = type_methods.is_some_and(|methods| methods.contains_key(method_name));
+        let universal = !overridden && self.universal_methods.contains_key(method_name);
+        if universal {
+            let key = format!("universal.{}", method_name);
+            self.check_arity("Method", &key, &qualified_name, args.len());
+        } else {
+            self.check_arity("Method", &qualified_name, &qualified_name, args.len());
+        }
+        if let (Value::Array(values), [needle]) = (&receiver, args.as_slice()) {
+            if SEARCH_METHODS.contains(&method_name) {
+                self.check_search_type(&qualified_name, &values.borrow(), needle);
+            }
+        }
+
+        if !universal {
+            let evaluator_method = self
+                .evaluator_type_methods(&receiver)
+                .and_then(|methods| methods.get(method_name).copied());
+            if let Some(method) = evaluator_method {
+                return method(self, &receiver, args);
+            }
+        }
+
+        let type_methods = self.type_methods(&receiver);
+        let method = if universal {
+            self.universal_methods.get(method_name)
+        } else {
+            type_methods.and_then(|methods| methods.get(method_name))
         };
 
         if let Some(method) = method {
             method(&receiver, args)
         } else {
+            let mut available: Vec<&String> = self
+                .universal_methods
+                .keys()
+                .chain(type_methods.into_iter().flat_map(|methods| methods.keys()))
+                .chain(
+                    self.evaluator_type_methods(&receiver)
+                        .into_iter()
+                        .flat_map(|methods| methods.keys()),
+                )
+                .collect();
+            available.sort();
+            available.dedup();
             runtime_error(&format!(
-                "Method '{}' not found for {:?}",
-                method_name, receiver
+                "Method '{}' not found for {}. Available methods: {}",
+                method_name,
+                receiver.type_name(),
+                available
+                    .iter()
+                    .map(|name| name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
             ))
         }
     }
+
+    // Arrays can hold mixed types, so only warn when no element could possibly match
+    fn check_search_type(&mut self, name: &str, values: &[Value], needle: &Value) {
+        if *needle == Value::Null || values.is_empty() {
+            return;
+        }
+        if values.iter().all(|v| v.type_name() != needle.type_name()) {
+            self.warn(&format!(
+                "{} is given a {}, but the array holds no {} values",
+                name,
+                needle.type_name(),
+                needle.type_name()
+            ));
+        }
+    }
+
+    fn check_arity(&mut self, kind: &str, key: &str, name: &str, count: usize) {
+        if let Some(&arity) = self.arities.get(key) {
+            if !arity.accepts(count) {
+                self.arity_error(kind, name, arity, count);
+            }
+        }
+    }
+
+    fn arity_error(&mut self, kind: &str, name: &str, arity: Arity, count: usize) -> ! {
+        let message = format!(
+            "{} '{}' expected {}, got {}",
+            kind,
+            name,
+            arity.describe(),
+            count
+        );
+        self.warn(&message);
+        runtime_error(&message)
+    }
+
+    // Extension point for methods that need to call back into the evaluator, such as `map`. To
+    // add one, insert it into the matching `*_evaluator_methods` map in stdlib.rs, and add a
+    // branch here if that receiver type doesn't have a map yet.
+    fn evaluator_type_methods(&self, receiver: &Value) -> Option<&EvaluatorMethodMap> {
+        match receiver {
+            Value::Array(_) => Some(&self.array_evaluator_methods),
+            _ => None,
+        }
+    }
+
+    fn type_methods(&self, receiver: &Value) -> Option<&MethodMap> {
+        match receiver {
+            Value::String(_) => Some(&self.string_methods),
+            Value::Number(_) => Some(&self.number_methods),
+            Value::Array(_) => Some(&self.array_methods),
+            Value::Object(_) => Some(&self.object_methods),
+            Value::Map(_) => Some(&self.map_methods),
+            Value::Set(_) => Some(&self.set_methods),
+            Value::Boolean(_) => Some(&self.boolean_methods),
+            _ => None,
+        }
+    }
     fn bin_op_error(&self, op: &TokenKind, left: &Value, right: &Value) -> Value {
         runtime_error(&format!(
-            "Unsupported binary operation: {:?} {:?} {:?}",
-            left, op, right
+            "Unsupported binary operation: {} {:?} {}",
+            left.type_name(),
+            op,
+            right.type_name()
         ))
     }
-    fn evaluate_binary_op(&mut self, op: &TokenKind, left: &ASTNode, right: &ASTNode) -> Value {
+    fn evaluate_binary_op(
+        &mut self,
+        op: &TokenKind,
+        left: &ASTNode,
+        right: &ASTNode,
+        position: (usize, usize),
+    ) -> Value {
         match op {
             TokenKind::And => {
                 let left_val = self.evaluate_node(left);
@@ -340,6 +1046,21 @@ impl TreeWalk {
                 let right_val = self.evaluate_node(right);
                 Value::Boolean(right_val.is_truthy())
             }
+            TokenKind::NullCoalesce => {
+                // Only null falls through to the right side, unlike `||` which also skips falsy values
+                let left_val = self.evaluate_node(left);
+                if left_val == Value::Null {
+                    self.evaluate_node(right)
+                } else {
+                    left_val
+                }
+            }
+            TokenKind::Assign
+            | TokenKind::PlusAssign
+            | TokenKind::MinusAssign
+            | TokenKind::StarAssign
+            | TokenKind::SlashAssign
+            | TokenKind::ModAssign => self.evaluate_assignment(op, left, right),
             _ => {
                 let left_val = self.evaluate_node(left);
                 if let Value::Return(_) = left_val {
@@ -349,13 +1070,20 @@ impl TreeWalk {
                 if let Value::Return(_) = right_val {
                     return right_val;
                 }
+                self.position = Some(position);
                 match op {
                     TokenKind::Plus => self.evaluate_addition(&left_val, &right_val),
                     TokenKind::Minus => self.evaluate_subtraction(&left_val, &right_val),
                     TokenKind::Star => self.evaluate_multiplication(&left_val, &right_val),
                     TokenKind::Slash => self.evaluate_division(&left_val, &right_val),
-                    TokenKind::Equal => Value::Boolean(left_val == right_val),
-                    TokenKind::NotEqual => Value::Boolean(left_val != right_val),
+                    TokenKind::Equal => {
+                        self.check_mixed_equality(&left_val, &right_val);
+                        Value::Boolean(left_val == right_val)
+                    }
+                    TokenKind::NotEqual => {
+                        self.check_mixed_equality(&left_val, &right_val);
+                        Value::Boolean(left_val != right_val)
+                    }
                     TokenKind::Greater => {
                         self.evaluate_comparison(&left_val, &right_val, |a, b| a > b)
                     }
@@ -371,40 +1099,133 @@ impl TreeWalk {
                     TokenKind::BitAnd => self.evaluate_bitwise_and(&left_val, &right_val),
                     TokenKind::BitOr => self.evaluate_bitwise_or(&left_val, &right_val),
                     TokenKind::BitXor => self.evaluate_bitwise_xor(&left_val, &right_val),
-                    TokenKind::Assign => match left {
-                        ASTNode::Variable(name) => {
-                            let right_val = self.evaluate_node(right);
-                            if !self.current_scope.borrow_mut().set(name, right_val.clone()) {
-                                runtime_error(&format!("Undefined variable: {}", name));
-                            }
-                            right_val
-                        }
-                        ASTNode::MemberAccess { object, member } => {
-                            let obj_val = self.evaluate_node(object);
-                            if let Value::Object(properties) = obj_val {
-                                properties
-                                    .borrow_mut()
-                                    .insert(member.clone(), self.evaluate_node(right));
-                                Value::Null
-                            } else {
-                                runtime_error("Attempted member access on non-object value")
-                            }
-                        }
-                        _ => runtime_error("Left side of assignment must be a variable"),
-                    },
-                    TokenKind::Mod => match (&left_val, &right_val) {
-                        (Value::Number(a), Value::Number(b)) => Value::Number(a % b),
-                        _ => self.bin_op_error(op, &left_val, &right_val),
-                    },
+                    TokenKind::Mod => self.evaluate_modulo(&left_val, &right_val),
                     _ => runtime_error(format!("Unknown binary operator: {:?}", op).as_str()),
                 }
             }
         }
     }
+    fn evaluate_assignment(&mut self, op: &TokenKind, target: &ASTNode, value: &ASTNode) -> Value {
+        let place = self.resolve_place(target);
+        let right_val = self.evaluate_node(value);
+        let new_val = match op {
+            TokenKind::Assign => right_val,
+            _ => {
+                let current = self.read_place(&place);
+                match op {
+                    TokenKind::PlusAssign => self.evaluate_addition(&current, &right_val),
+                    TokenKind::MinusAssign => self.evaluate_subtraction(&current, &right_val),
+                    TokenKind::StarAssign => self.evaluate_multiplication(&current, &right_val),
+                    TokenKind::SlashAssign => self.evaluate_division(&current, &right_val),
+                    _ => self.evaluate_modulo(&current, &right_val),
+                }
+            }
+        };
+        self.write_place(&place, new_val.clone());
+        new_val
+    }
+
+    fn resolve_place(&mut self, target: &ASTNode) -> Place {
+        match target {
+            ASTNode::Variable(name) => Place::Variable(name.clone()),
+            ASTNode::MemberAccess { object, member } => match self.evaluate_node(object) {
+                Value::Object(properties) => Place::Property(properties, member.clone()),
+                _ => runtime_error("Attempted member access on non-object value"),
+            },
+            ASTNode::Index { object, index } => {
+                let obj_val = self.evaluate_node(object);
+                let index_val = self.evaluate_node(index);
+                match (obj_val, index_val) {
+                    (Value::Array(values), Value::Number(i)) => {
+                        let length = values.borrow().len();
+                        match resolve_index(i, length) {
+                            Some(i) => Place::Element(values, i),
+                            None => runtime_error(&format!(
+                                "Index out of bounds: index {}, length {}",
+                                i, length
+                            )),
+                        }
+                    }
+                    (Value::Object(properties), Value::String(key)) => {
+                        Place::Property(properties, key)
+                    }
+                    (Value::Map(entries), key) => Place::Entry(entries, map_key("Map index", &key)),
+                    (obj_val, index_val) => runtime_error(&format!(
+                        "Cannot assign to index of {} with {}",
+                        obj_val.type_name(),
+                        index_val.type_name()
+                    )),
+                }
+            }
+            _ => runtime_error("Left side of assignment must be a variable"),
+        }
+    }
+
+    fn read_place(&self, place: &Place) -> Value {
+        match place {
+            Place::Variable(name) => self
+                .current_scope
+                .borrow()
+                .get(name)
+                .unwrap_or_else(|| runtime_error(&format!("Undefined variable: {}", name))),
+            Place::Property(properties, key) => match properties.borrow().get(key) {
+                Some(val) => val.clone(),
+                None => runtime_error(&format!("Property '{}' not found", key)),
+            },
+            Place::Element(values, i) => {
+                let values = values.borrow();
+                check_element(*i, values.len());
+                values[*i].clone()
+            }
+            Place::Entry(entries, key) => entries.borrow().get(key).cloned().unwrap_or(Value::Null),
+        }
+    }
+
+    fn write_place(&mut self, place: &Place, value: Value) {
+        self.trace("BinaryOp", || TraceKind::Assignment {
+            name: match place {
+                Place::Variable(name) => name.clone(),
+                Place::Property(_, key) => format!(".{}", key),
+                Place::Element(_, i) => format!("[{}]", i),
+                Place::Entry(_, key) => format!("[{}]", key.to_value()),
+            },
+            value: value.clone(),
+        });
+        match place {
+            Place::Variable(name) => {
+                if !self.current_scope.borrow_mut().set(name, value) {
+                    runtime_error(&format!("Undefined variable: {}", name));
+                }
+            }
+            Place::Property(properties, key) => {
+                properties.borrow().ensure_mutable();
+                properties.borrow_mut().insert(key.clone(), value);
+            }
+            Place::Element(values, i) => {
+                values.borrow().ensure_mutable();
+                let length = values.borrow().len();
+                check_element(*i, length);
+                values.borrow_mut()[*i] = value;
+            }
+            Place::Entry(entries, key) => {
+                entries.borrow().ensure_mutable();
+                entries.borrow_mut().insert(key.clone(), value);
+            }
+        }
+    }
+
     fn evaluate_addition(&self, left_val: &Value, right_val: &Value) -> Value {
         match (left_val, right_val) {
             (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
             (Value::String(a), Value::String(b)) => Value::String(a.clone() + b),
+            // A string on either side turns the addition into concatenation, with the other
+            // operand written the way it prints
+            (Value::String(a), Value::Number(_) | Value::Boolean(_) | Value::Null) => {
+                Value::String(format!("{}{}", a, right_val))
+            }
+            (Value::Number(_) | Value::Boolean(_) | Value::Null, Value::String(b)) => {
+                Value::String(format!("{}{}", left_val, b))
+            }
             _ => self.bin_op_error(&TokenKind::Plus, left_val, right_val),
         }
     }
@@ -430,6 +1251,13 @@ impl TreeWalk {
         }
     }
 
+    fn evaluate_modulo(&self, left_val: &Value, right_val: &Value) -> Value {
+        match (left_val, right_val) {
+            (Value::Number(a), Value::Number(b)) => Value::Number(a % b),
+            _ => self.bin_op_error(&TokenKind::Mod, left_val, right_val),
+        }
+    }
+
     fn evaluate_bitwise_and(&self, left_val: &Value, right_val: &Value) -> Value {
         match (left_val, right_val) {
             (Value::Number(a), Value::Number(b)) => {
@@ -457,16 +1285,43 @@ impl TreeWalk {
         }
     }
 
-    fn evaluate_comparison<F>(&self, left_val: &Value, right_val: &Value, cmp: F) -> Value
+    fn evaluate_comparison<F>(&mut self, left_val: &Value, right_val: &Value, cmp: F) -> Value
     where
         F: Fn(f64, f64) -> bool,
     {
         match (left_val, right_val) {
             (Value::Number(a), Value::Number(b)) => Value::Boolean(cmp(*a, *b)),
-            _ => self.bin_op_error(&TokenKind::Greater, left_val, right_val),
+            _ => {
+                if left_val.type_name() != right_val.type_name() {
+                    self.warn(&format!(
+                        "Ordering comparison between {} and {}",
+                        left_val.type_name(),
+                        right_val.type_name()
+                    ));
+                }
+                self.bin_op_error(&TokenKind::Greater, left_val, right_val)
+            }
+        }
+    }
+
+    fn check_mixed_equality(&mut self, left_val: &Value, right_val: &Value) {
+        // Comparing against null is the usual way to test for absence, so it never warns
+        if *left_val == Value::Null || *right_val == Value::Null {
+            return;
+        }
+        if left_val.type_name() != right_val.type_name() {
+            self.warn(&format!(
+                "Equality comparison between {} and {} is always false",
+                left_val.type_name(),
+                right_val.type_name()
+            ));
         }
     }
     fn evaluate_unary_op(&mut self, op: &TokenKind, operand: &ASTNode) -> Value {
+        if let TokenKind::Inc | TokenKind::Dec = op {
+            return self.evaluate_increment(op, operand, false);
+        }
+
         let val = self.evaluate_node(operand);
         if let Value::Return(_) = val {
             return val;
@@ -476,35 +1331,54 @@ impl TreeWalk {
                 Value::Number(n) => Value::Number(-n),
                 _ => runtime_error("Operand must be a number"),
             },
-            TokenKind::Bang => match val {
-                Value::Boolean(b) => Value::Boolean(!b),
-                _ => runtime_error("Operand must be a boolean"),
-            },
-            TokenKind::Inc => match val {
-                Value::Number(n) => {
-                    let new_val = Value::Number(n + 1.0);
-                    if let ASTNode::Variable(name) = operand {
-                        if !self.current_scope.borrow_mut().set(name, new_val.clone()) {
-                            runtime_error(&format!("Undefined variable: {}", name));
-                        }
-                    }
-                    new_val
-                }
-                _ => runtime_error("Operand must be a number"),
-            },
-            TokenKind::Dec => match val {
-                Value::Number(n) => {
-                    let new_val = Value::Number(n - 1.0);
-                    if let ASTNode::Variable(name) = operand {
-                        if !self.current_scope.borrow_mut().set(name, new_val.clone()) {
-                            runtime_error(&format!("Undefined variable: {}", name));
-                        }
-                    }
-                    new_val
-                }
-                _ => runtime_error("Operand must be a number"),
-            },
+            TokenKind::Bang => Value::Boolean(!val.is_truthy()),
             _ => runtime_error(format!("Unknown unary operator: {:?}", op).as_str()),
         }
     }
+
+    // Adds or subtracts one in place. Prefix forms return the updated value, postfix forms the
+    // value from before
+    fn evaluate_increment(&mut self, op: &TokenKind, operand: &ASTNode, postfix: bool) -> Value {
+        let place = self.resolve_place(operand);
+        let (old_val, new_val) = match self.read_place(&place) {
+            Value::Number(n) if *op == TokenKind::Inc => (n, n + 1.0),
+            Value::Number(n) => (n, n - 1.0),
+            _ => runtime_error("Operand must be a number"),
+        };
+        self.write_place(&place, Value::Number(new_val));
+        Value::Number(if postfix { old_val } else { new_val })
+    }
+}
+
+// Named function declarations are bound by `hoist_functions`, and skipped where they appear
+fn is_hoisted(stmt: &ASTNode) -> bool {
+    matches!(stmt, ASTNode::FunctionDeclaration { name: Some(_), .. })
+}
+
+// Whether a `break` or `continue` with `target` applies to a loop labelled `label`
+fn targets_loop(target: &Option<String>, label: &Option<String>) -> bool {
+    target.is_none() || target == label
+}
+
+// How a call site refers to its callee, e.g. `make_user` or `person.greet`
+fn callee_name(callee: &ASTNode) -> String {
+    match callee {
+        ASTNode::Variable(name) => name.clone(),
+        ASTNode::MemberAccess { object, member } => match object.as_ref() {
+            ASTNode::Variable(object) => format!("{}.{}", object, member),
+            _ => member.clone(),
+        },
+        _ => "<anonymous>".to_string(),
+    }
+}
+
+// A resolved element index can go stale when evaluating the right-hand side shrinks the array,
+// as in `a[2] = a.pop()`
+fn check_element(index: usize, length: usize) {
+    if index >= length {
+        runtime_error(&format!(
+            "Index out of bounds: index {}, length {}",
+            index, length
+        ));
+    }
 }
@@ -0,0 +1,144 @@
+use crate::treewalk::evaluator::runtime_error;
+use crate::treewalk::stdlib::{arities, Arity, StdMethod};
+use crate::treewalk::value::{ObjectMap, Value};
+use regex::{Captures, Regex};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// Compiled patterns are kept so a regex used in a loop is only compiled once. The cache is
+// cleared when it grows past this many patterns, so generated patterns can't grow it forever.
+const MAX_CACHED_PATTERNS: usize = 256;
+
+thread_local! {
+    static PATTERN_CACHE: RefCell<HashMap<String, Regex>> = RefCell::new(HashMap::new());
+}
+
+pub fn regex_methods() -> HashMap<String, StdMethod> {
+    let mut methods: HashMap<String, StdMethod> = HashMap::new();
+
+    /*
+    Description of the methods:
+    - regex_match: Returns whether the pattern matches anywhere in the string.
+    - regex_find: Returns the first match as an object {matched, start, end, groups}, or null.
+    - regex_find_all: Returns an array of every non-overlapping match, as regex_find describes.
+    - regex_replace: Replaces every match with the replacement, in which $1 or ${name} refers to
+      a capture group.
+    Positions are character indices, the same as the string methods use. An invalid pattern raises
+    a runtime error with the reason it was rejected.
+    */
+
+    methods.insert(
+        "regex_match".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let (pattern, s) = (
+                string("regex_match", &args, 0),
+                string("regex_match", &args, 1),
+            );
+            Value::Boolean(with_regex(pattern, |regex| regex.is_match(s)))
+        },
+    );
+    methods.insert(
+        "regex_find".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let (pattern, s) = (
+                string("regex_find", &args, 0),
+                string("regex_find", &args, 1),
+            );
+            with_regex(pattern, |regex| {
+                regex
+                    .captures(s)
+                    .map_or(Value::Null, |captures| match_object(s, &captures))
+            })
+        },
+    );
+    methods.insert(
+        "regex_find_all".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let pattern = string("regex_find_all", &args, 0);
+            let s = string("regex_find_all", &args, 1);
+            let matches: Vec<Value> = with_regex(pattern, |regex| {
+                regex
+                    .captures_iter(s)
+                    .map(|captures| match_object(s, &captures))
+                    .collect()
+            });
+            Value::Array(Rc::new(RefCell::new(matches.into())))
+        },
+    );
+    methods.insert(
+        "regex_replace".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let pattern = string("regex_replace", &args, 0);
+            let s = string("regex_replace", &args, 1);
+            let replacement = string("regex_replace", &args, 2);
+            Value::String(with_regex(pattern, |regex| {
+                regex.replace_all(s, replacement).into_owned()
+            }))
+        },
+    );
+    methods
+}
+
+pub fn regex_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("regex_match", 2, Some(2)),
+        ("regex_find", 2, Some(2)),
+        ("regex_find_all", 2, Some(2)),
+        ("regex_replace", 3, Some(3)),
+    ])
+}
+
+fn string<'a>(name: &str, args: &'a [Value], index: usize) -> &'a str {
+    match &args[index] {
+        Value::String(s) => s,
+        other => runtime_error(
+            format!(
+                "`{}` argument {} must be a string: got {}",
+                name,
+                index + 1,
+                other.type_name(),
+            )
+            .as_str(),
+        ),
+    }
+}
+
+fn with_regex<T>(pattern: &str, f: impl FnOnce(&Regex) -> T) -> T {
+    let regex = PATTERN_CACHE.with(|cache| {
+        if let Some(regex) = cache.borrow().get(pattern) {
+            return regex.clone();
+        }
+        let regex = Regex::new(pattern).unwrap_or_else(|e| {
+            runtime_error(format!("Invalid regular expression: {}", e).as_str())
+        });
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= MAX_CACHED_PATTERNS {
+            cache.clear();
+        }
+        cache.insert(pattern.to_string(), regex.clone());
+        regex
+    });
+    f(&regex)
+}
+
+fn match_object(s: &str, captures: &Captures) -> Value {
+    let whole = captures.get(0).unwrap();
+    let char_index = |byte_index: usize| Value::Number(s[..byte_index].chars().count() as f64);
+    // Groups that didn't take part in the match are null
+    let groups: Vec<Value> = captures
+        .iter()
+        .skip(1)
+        .map(|group| group.map_or(Value::Null, |g| Value::String(g.as_str().to_string())))
+        .collect();
+    let fields: ObjectMap = [
+        ("matched", Value::String(whole.as_str().to_string())),
+        ("start", char_index(whole.start())),
+        ("end", char_index(whole.end())),
+        ("groups", Value::Array(Rc::new(RefCell::new(groups.into())))),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_string(), value))
+    .collect();
+    Value::Object(Rc::new(RefCell::new(fields.into())))
+}
@@ -1,11 +1,174 @@
-use crate::treewalk::evaluator::runtime_error;
-use crate::treewalk::value::Value;
+use crate::errors::EvalError;
+use crate::treewalk::evaluator::{runtime_error, TreeWalk};
+use crate::treewalk::value::{
+    deep_eq, format_number, freeze, resolve_index, Freezable, MapKey, ObjectMap, OrderedMap,
+    OrderedSet, Value,
+};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore, SeedableRng};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::Write;
 use std::rc::Rc;
 
 pub type StdMethod = fn(&Value, Vec<Value>) -> Value;
+/// Number of arguments a native function or method accepts, checked before it is called.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Arity {
+    pub min: usize,
+    pub max: Option<usize>, // None means any number of arguments
+}
+
+impl Arity {
+    pub fn accepts(&self, count: usize) -> bool {
+        count >= self.min && self.max.is_none_or(|max| count <= max)
+    }
+    pub fn describe(&self) -> String {
+        let plural = |n: usize| if n == 1 { "argument" } else { "arguments" };
+        match self.max {
+            Some(max) if max == self.min => format!("{} {}", max, plural(max)),
+            Some(max) => format!("{} to {} {}", self.min, max, plural(max)),
+            None => format!("at least {} {}", self.min, plural(self.min)),
+        }
+    }
+}
+
+pub(crate) fn arities(table: &[(&str, usize, Option<usize>)]) -> HashMap<String, Arity> {
+    table
+        .iter()
+        .map(|&(name, min, max)| (name.to_string(), Arity { min, max }))
+        .collect()
+}
+
+// Like StdMethod, but with access to the evaluator so it can call functions passed as arguments
+pub type EvaluatorMethod = fn(&mut TreeWalk, &Value, Vec<Value>) -> Value;
+
+// Largest array std.range, std.fill and resize will build, so a typo can't exhaust the host's
+// memory
+const MAX_RANGE_LENGTH: usize = 10_000_000;
+
+// The zero point of std.clock, set when the standard library is first loaded
+static CLOCK_START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+
+fn since_epoch() -> std::time::Duration {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+}
+
+// Offset of local time from UTC in seconds at the given moment, taken from the C library so
+// daylight saving time is accounted for. Other platforms use UTC.
+#[cfg(unix)]
+fn local_offset(epoch_seconds: i64) -> i64 {
+    let time = epoch_seconds as libc::time_t;
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    // SAFETY: both pointers are valid for the duration of the call, and localtime_r is reentrant
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        0
+    } else {
+        tm.tm_gmtoff as i64
+    }
+}
+
+#[cfg(not(unix))]
+fn local_offset(_epoch_seconds: i64) -> i64 {
+    0
+}
+
+/// A calendar date and time of day, in whatever time zone the epoch seconds were shifted to.
+struct DateTime {
+    year: i64,
+    month: i64,
+    day: i64,
+    hour: i64,
+    minute: i64,
+    second: i64,
+}
+
+impl DateTime {
+    fn from_epoch(seconds: i64) -> Self {
+        let days = seconds.div_euclid(86400);
+        let time = seconds.rem_euclid(86400);
+        // Days to a proleptic Gregorian date, counting from 0000-03-01 so the leap day falls at
+        // the end of each year. See http://howardhinnant.github.io/date_algorithms.html
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let day_of_era = z.rem_euclid(146097);
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let shifted_month = (5 * day_of_year + 2) / 153;
+        let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+        let month = if shifted_month < 10 {
+            shifted_month + 3
+        } else {
+            shifted_month - 9
+        };
+        let year = year_of_era + era * 400 + i64::from(month <= 2);
+        DateTime {
+            year,
+            month,
+            day,
+            hour: time / 3600,
+            minute: time % 3600 / 60,
+            second: time % 60,
+        }
+    }
+
+    fn format(&self, format: &str) -> String {
+        let mut result = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                result.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('Y') => result.push_str(&format!("{:04}", self.year)),
+                Some('m') => result.push_str(&format!("{:02}", self.month)),
+                Some('d') => result.push_str(&format!("{:02}", self.day)),
+                Some('H') => result.push_str(&format!("{:02}", self.hour)),
+                Some('M') => result.push_str(&format!("{:02}", self.minute)),
+                Some('S') => result.push_str(&format!("{:02}", self.second)),
+                Some('%') => result.push('%'),
+                Some(other) => runtime_error(
+                    format!("`format_time` does not support the specifier '%{}'", other).as_str(),
+                ),
+                None => runtime_error("`format_time` format ends with a lone '%'"),
+            }
+        }
+        result
+    }
+}
+
+fn date_time_object(time: &DateTime) -> Value {
+    let fields = [
+        ("year", time.year),
+        ("month", time.month),
+        ("day", time.day),
+        ("hour", time.hour),
+        ("minute", time.minute),
+        ("second", time.second),
+    ];
+    let object: ObjectMap = fields
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), Value::Number(value as f64)))
+        .collect();
+    Value::Object(Rc::new(RefCell::new(object.into())))
+}
+
+thread_local! {
+    // Set by `std.seed`, until then the random functions draw from the thread's own generator
+    static SEEDED_RNG: RefCell<Option<StdRng>> = const { RefCell::new(None) };
+}
+
+fn with_rng<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    SEEDED_RNG.with(|seeded| match seeded.borrow_mut().as_mut() {
+        Some(rng) => f(rng),
+        None => f(&mut rand::thread_rng()),
+    })
+}
 
 pub fn std_methods() -> HashMap<String, StdMethod> {
     // For the included 'std' object, E.G. std.time()
@@ -13,27 +176,156 @@ pub fn std_methods() -> HashMap<String, StdMethod> {
     /*
     Description of the methods:
     - time: Returns the current time in seconds since the Unix epoch.
+    - time_millis: Returns the current time in whole milliseconds since the Unix epoch.
+    - clock: Returns the seconds elapsed on a monotonic clock since the program started, for
+      measuring durations.
+    - now: Returns the local date and time as an object with year, month, day, hour, minute and
+      second.
+    - utcnow: Like now, in UTC.
+    - format_time: Formats epoch seconds with %Y, %m, %d, %H, %M, %S and %%, in local time unless
+      the optional third argument is true, in which case UTC is used.
     - random: Returns a random number between 0 and 1.
+    - random_int: Returns a random integer between the two arguments, inclusive.
+    - choice: Returns a random element of a non-empty array.
+    - shuffle: Shuffles an array in place.
+    - seed: Makes the random functions deterministic, producing the same sequence for a seed.
     - print: Prints the arguments to stdout.
     - println: Prints the arguments to stdout followed by a newline.
     - argv: Returns the command line arguments as an array of strings.
-    - get_line: Reads a line from stdin.
+    - env_get: Returns the value of an environment variable, or null if it is not set.
+    - env_set: Sets an environment variable for this process and the processes it starts.
+    - env_vars: Returns an object of all environment variables.
+    - get_line: Reads a line from stdin, including its line break.
+    - input: Prints the optional prompt, then reads a line from stdin without its line break.
+      Returns null at the end of input.
     - write_file: Writes the second argument to the file specified by the first argument.
     - read_file: Reads the contents of the file specified by the first argument.
-    - exit: Exits the program with the given exit code.
+    - append_file: Appends the second argument to the file specified by the first, creating it.
+    - file_exists: Returns whether a file or directory exists at the given path.
+    - delete_file: Deletes the file at the given path.
+    - list_dir: Returns the sorted names of the entries in the given directory.
+    - mkdir: Creates the given directory along with any missing parents, raising a runtime error
+      with the OS error message if it fails, as the other file functions do.
+    - sleep: Pauses the program for the given number of seconds, which may be fractional.
+    - default: Returns the second argument if the first is null, and the first otherwise.
+    - map: Creates a Map, optionally filled from an array of [key, value] pairs.
+    - set: Creates a Set, optionally from the elements of an array with duplicates dropped.
+    - enumerate: Returns an array of [index, value] pairs for the elements of an array.
+    - zip: Returns an array of [a[i], b[i]] pairs, as long as the shorter of the two arrays.
+    - system: Runs a command through the shell (sh -c, or cmd /C on Windows) and returns an object
+      with its stdout, stderr and exit code.
+    - spawn: Runs a program with an array of arguments, without a shell, returning the same object.
+    - exec: An alias of spawn whose array of arguments is required. Errors name whichever of the
+      two was called.
+    - base64_encode: Encodes the UTF-8 bytes of a string as standard, padded base64.
+    - base64_decode: Decodes base64 back into a string.
+    - hex_encode: Encodes the UTF-8 bytes of a string as lowercase hex.
+    - hex_decode: Decodes hex, in either case, back into a string.
+    Decoding raises a runtime error for invalid input or bytes that aren't valid UTF-8.
+    - hash: Returns a 53-bit FNV-1a hash of a value, equal for deep-equal values on every platform.
+    - sha256: Returns the SHA-256 digest of the UTF-8 bytes of a string as lowercase hex. Only
+      available with the crypto feature.
+    - exit: Exits the program with the given exit code, 0 by default, after flushing stdout.
+    - deep_eq: Returns whether two values are structurally equal, recursing into arrays and objects.
+    - freeze: Makes an array or object read-only and returns it.
+    - deep_freeze: Like freeze, but also freezes every array or object nested inside.
+    - chr: Returns the one-character string for the given code point, the inverse of ord.
+    - range: Returns an array of numbers from start (default 0) up to, not including, end, counting
+      by step (default 1). A negative step counts down.
+    - fill: Returns an array holding the value the given number of times.
+    - assert: Raises a runtime error, with the optional message, if the first argument is falsy.
     */
 
     let mut methods: HashMap<String, StdMethod> = HashMap::new();
+    CLOCK_START.get_or_init(std::time::Instant::now);
     methods.insert("time".to_string(), |_this: &Value, _args: Vec<Value>| {
-        Value::Number(
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs_f64(),
-        )
+        Value::Number(since_epoch().as_secs_f64())
+    });
+    methods.insert(
+        "time_millis".to_string(),
+        |_this: &Value, _args: Vec<Value>| Value::Number(since_epoch().as_millis() as f64),
+    );
+    methods.insert("clock".to_string(), |_this: &Value, _args: Vec<Value>| {
+        let start = CLOCK_START.get_or_init(std::time::Instant::now);
+        Value::Number(start.elapsed().as_secs_f64())
     });
+    methods.insert("now".to_string(), |_this: &Value, _args: Vec<Value>| {
+        let seconds = since_epoch().as_secs() as i64;
+        date_time_object(&DateTime::from_epoch(seconds + local_offset(seconds)))
+    });
+    methods.insert("utcnow".to_string(), |_this: &Value, _args: Vec<Value>| {
+        date_time_object(&DateTime::from_epoch(since_epoch().as_secs() as i64))
+    });
+    methods.insert(
+        "format_time".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let seconds = number_arg("format_time", &args, 0);
+            if !seconds.is_finite() {
+                runtime_error(
+                    format!(
+                        "`format_time` called with an invalid time: {}",
+                        format_number(seconds)
+                    )
+                    .as_str(),
+                )
+            }
+            let format = string_argument("format_time", &args, 1);
+            let utc = args.get(2).is_some_and(Value::is_truthy);
+            let seconds = seconds.floor() as i64;
+            let offset = if utc { 0 } else { local_offset(seconds) };
+            Value::String(DateTime::from_epoch(seconds + offset).format(format))
+        },
+    );
     methods.insert("random".to_string(), |_this: &Value, _args: Vec<Value>| {
-        Value::Number(rand::random::<f64>())
+        Value::Number(with_rng(|rng| rng.gen::<f64>()))
+    });
+    methods.insert(
+        "random_int".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let min = number_arg("random_int", &args, 0);
+            let max = number_arg("random_int", &args, 1);
+            if min.fract() != 0.0 || max.fract() != 0.0 {
+                runtime_error(
+                    format!(
+                        "`random_int` bounds must be integers: got {} and {}",
+                        format_number(min),
+                        format_number(max)
+                    )
+                    .as_str(),
+                )
+            }
+            if min > max {
+                runtime_error(
+                    format!(
+                        "`random_int` min must not be greater than max: got {} and {}",
+                        format_number(min),
+                        format_number(max)
+                    )
+                    .as_str(),
+                )
+            }
+            let n = with_rng(|rng| rng.gen_range(min as i64..=max as i64));
+            Value::Number(n as f64)
+        },
+    );
+    methods.insert("choice".to_string(), |_this: &Value, args: Vec<Value>| {
+        let values = array_arg("choice", &args, 0).borrow();
+        if values.is_empty() {
+            runtime_error("`choice` called on an empty array")
+        }
+        let index = with_rng(|rng| rng.gen_range(0..values.len()));
+        values[index].clone()
+    });
+    methods.insert("shuffle".to_string(), |_this: &Value, args: Vec<Value>| {
+        let values = array_arg("shuffle", &args, 0);
+        values.borrow().ensure_mutable();
+        with_rng(|rng| values.borrow_mut().shuffle(rng));
+        Value::Null
+    });
+    methods.insert("seed".to_string(), |_this: &Value, args: Vec<Value>| {
+        let seed = number_arg("seed", &args, 0);
+        SEEDED_RNG.with(|rng| *rng.borrow_mut() = Some(StdRng::seed_from_u64(seed.to_bits())));
+        Value::Null
     });
     methods.insert("print".to_string(), |_this: &Value, args: Vec<Value>| {
         for arg in args.iter() {
@@ -52,8 +344,41 @@ pub fn std_methods() -> HashMap<String, StdMethod> {
     });
     methods.insert("argv".to_string(), |_this: &Value, _args: Vec<Value>| {
         let args: Vec<Value> = std::env::args().map(Value::String).collect();
-        Value::Array(Rc::new(RefCell::new(args)))
+        Value::Array(Rc::new(RefCell::new(args.into())))
+    });
+    methods.insert(
+        "env_get".to_string(),
+        |_this: &Value, args: Vec<Value>| match std::env::var_os(string_argument(
+            "env_get", &args, 0,
+        )) {
+            Some(value) => Value::String(value.to_string_lossy().into_owned()),
+            None => Value::Null,
+        },
+    );
+    methods.insert("env_set".to_string(), |_this: &Value, args: Vec<Value>| {
+        let name = string_argument("env_set", &args, 0);
+        let value = string_argument("env_set", &args, 1);
+        // set_var panics on names it can't represent, report those as runtime errors instead
+        if name.is_empty() || name.contains('=') || name.contains('\0') || value.contains('\0') {
+            runtime_error(format!("env_set called with an invalid variable: '{}'", name).as_str())
+        }
+        std::env::set_var(name, value);
+        Value::Null
     });
+    methods.insert(
+        "env_vars".to_string(),
+        |_this: &Value, _args: Vec<Value>| {
+            let vars = std::env::vars_os()
+                .map(|(name, value)| {
+                    (
+                        name.to_string_lossy().into_owned(),
+                        Value::String(value.to_string_lossy().into_owned()),
+                    )
+                })
+                .collect::<ObjectMap>();
+            Value::Object(Rc::new(RefCell::new(vars.into())))
+        },
+    );
     methods.insert(
         "get_line".to_string(),
         |_this: &Value, _args: Vec<Value>| {
@@ -66,190 +391,931 @@ pub fn std_methods() -> HashMap<String, StdMethod> {
             }
         },
     );
+    methods.insert("input".to_string(), |_this: &Value, args: Vec<Value>| {
+        if let Some(prompt) = args.first() {
+            prompt.print();
+            let _ = std::io::stdout().flush();
+        }
+        let mut input = String::new();
+        match std::io::stdin().read_line(&mut input) {
+            Ok(0) => Value::Null,
+            Ok(_) => {
+                let line = input.strip_suffix('\n').unwrap_or(&input);
+                let line = line.strip_suffix('\r').unwrap_or(line);
+                Value::String(line.to_string())
+            }
+            Err(e) => runtime_error(format!("input() failed to read stdin: {}", e).as_str()),
+        }
+    });
     methods.insert(
         "write_file".to_string(),
         |_this: &Value, args: Vec<Value>| {
-            if let Value::String(file) = &args[0] {
-                if let Value::String(contents) = &args[1] {
-                    if let Ok(mut file) = std::fs::File::create(file) {
-                        if let Err(e) = file.write_all(contents.as_bytes()) {
-                            eprintln!("Error writing to file: {}", e);
-                        }
-                    } else {
-                        eprintln!("Error creating file");
-                    }
-                    Value::Null
-                } else {
-                    runtime_error(
-                        format!("write_file contents must be a string: got {:?}", args[1]).as_str(),
-                    )
-                }
-            } else {
-                runtime_error(
-                    format!("write_file file path must be a string: got {:?}", args[0]).as_str(),
-                )
+            let path = string_argument("write_file", &args, 0);
+            let contents = string_argument("write_file", &args, 1);
+            if let Err(e) = std::fs::write(path, contents) {
+                io_error("write_file", path, e);
             }
+            Value::Null
         },
     );
     methods.insert(
         "read_file".to_string(),
         |_this: &Value, args: Vec<Value>| {
-            if let Value::String(file) = &args[0] {
-                match std::fs::read_to_string(file) {
-                    Ok(contents) => Value::String(contents),
-                    Err(e) => {
-                        eprintln!("Error reading file: {}", e);
-                        Value::Null
-                    }
-                }
-            } else {
-                runtime_error(
-                    format!("read_file file path must be a string: got {:?}", args[0]).as_str(),
-                )
+            let path = string_argument("read_file", &args, 0);
+            match std::fs::read_to_string(path) {
+                Ok(contents) => Value::String(contents),
+                Err(e) => io_error("read_file", path, e),
             }
         },
     );
-    methods.insert("exit".to_string(), |_this: &Value, args: Vec<Value>| {
-        if let Value::Number(code) = args.first().unwrap_or(&Value::Null) {
-            std::process::exit(*code as i32);
-        } else {
-            runtime_error("exit() argument must be a number")
-        }
+    methods.insert(
+        "append_file".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let path = string_argument("append_file", &args, 0);
+            let contents = string_argument("append_file", &args, 1);
+            let result = std::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)
+                .and_then(|mut file| file.write_all(contents.as_bytes()));
+            if let Err(e) = result {
+                io_error("append_file", path, e);
+            }
+            Value::Null
+        },
+    );
+    methods.insert(
+        "file_exists".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            Value::Boolean(std::path::Path::new(string_argument("file_exists", &args, 0)).exists())
+        },
+    );
+    methods.insert(
+        "delete_file".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let path = string_argument("delete_file", &args, 0);
+            if let Err(e) = std::fs::remove_file(path) {
+                io_error("delete_file", path, e);
+            }
+            Value::Null
+        },
+    );
+    methods.insert("list_dir".to_string(), |_this: &Value, args: Vec<Value>| {
+        let path = string_argument("list_dir", &args, 0);
+        let entries = std::fs::read_dir(path).unwrap_or_else(|e| io_error("list_dir", path, e));
+        let mut names: Vec<String> = entries
+            .map(|entry| match entry {
+                Ok(entry) => entry.file_name().to_string_lossy().into_owned(),
+                Err(e) => io_error("list_dir", path, e),
+            })
+            .collect();
+        names.sort();
+        let names: Vec<Value> = names.into_iter().map(Value::String).collect();
+        Value::Array(Rc::new(RefCell::new(names.into())))
     });
-    methods
-}
-
-pub fn string_methods() -> HashMap<String, StdMethod> {
-    let mut methods: HashMap<String, StdMethod> = HashMap::new();
-
-    /*
-    Description of the methods:
-    - length: Returns the length of the string.
-    - ord: Returns the ASCII value of the first character in the string.
-    - get: Returns the character at the given index.
-    - to_int: Converts the string to an integer.
-    - to_float: Converts the string to a float.
-    - replace: Replaces all occurrences of the first argument with the second argument.
-    - split: Splits the string by the given separator.
-    - find: Returns the index of the first occurrence of the given string.
-    */
-
-    methods.insert("length".to_string(), |this: &Value, _args: Vec<Value>| {
-        if let Value::String(s) = this {
-            Value::Number(s.len() as f64)
-        } else {
-            runtime_error(
-                format!(
-                    "`length` method called on non-string value: expected String, got {:?}",
-                    this,
-                )
-                .as_str(),
-            )
+    methods.insert("mkdir".to_string(), |_this: &Value, args: Vec<Value>| {
+        let path = string_argument("mkdir", &args, 0);
+        if let Err(e) = std::fs::create_dir_all(path) {
+            io_error("mkdir", path, e);
         }
+        Value::Null
     });
-    methods.insert("ord".to_string(), |this: &Value, _args: Vec<Value>| {
-        if let Value::String(s) = this {
-            if s.len() == 1 {
-                Value::Number(s.chars().next().unwrap() as u32 as f64)
-            } else {
-                runtime_error("ord() called on string with length != 1")
-            }
-        } else {
+    methods.insert("sleep".to_string(), |_this: &Value, args: Vec<Value>| {
+        let seconds = number_arg("sleep", &args, 0);
+        let duration = std::time::Duration::try_from_secs_f64(seconds).unwrap_or_else(|_| {
             runtime_error(
                 format!(
-                    "`ord` method called on non-string value: expected String, got {:?}",
-                    this,
+                    "`sleep` duration must be a non-negative number of seconds: got {}",
+                    format_number(seconds)
                 )
                 .as_str(),
             )
-        }
+        });
+        std::thread::sleep(duration);
+        Value::Null
     });
-    methods.insert("get".to_string(), |this: &Value, args: Vec<Value>| {
-        if let Value::String(s) = this {
-            if let Value::Number(i) = args[0] {
-                let i = i as i64;
-                if i >= 0 && i < s.len() as i64 {
-                    Value::String(s.chars().nth(i as usize).unwrap().to_string())
-                } else {
-                    runtime_error(
-                        format!(
-                            "Index out of bounds in `get` method: index {}, length {}",
-                            i,
-                            s.len(),
-                        )
-                        .as_str(),
-                    )
+    methods.insert(
+        "default".to_string(),
+        |_this: &Value, args: Vec<Value>| match &args[0] {
+            Value::Null => args[1].clone(),
+            value => value.clone(),
+        },
+    );
+    methods.insert("map".to_string(), |_this: &Value, args: Vec<Value>| {
+        let mut map = OrderedMap::new();
+        if !args.is_empty() {
+            for pair in array_arg("map", &args, 0).borrow().iter() {
+                match pair {
+                    Value::Array(pair) if pair.borrow().len() == 2 => {
+                        let pair = pair.borrow();
+                        map.insert(map_key("map", &pair[0]), pair[1].clone());
+                    }
+                    other => runtime_error(
+                        format!("`map` entries must be [key, value] pairs: got {}", other).as_str(),
+                    ),
                 }
-            } else {
-                runtime_error(
-                    format!("Index must be a number in `get` method: got {:?}", args[0]).as_str(),
-                )
             }
+        }
+        Value::Map(Rc::new(RefCell::new(map.into())))
+    });
+    methods.insert("set".to_string(), |_this: &Value, args: Vec<Value>| {
+        let items = match args.first() {
+            Some(_) => array_arg("set", &args, 0)
+                .borrow()
+                .iter()
+                .cloned()
+                .collect(),
+            None => OrderedSet::new(),
+        };
+        new_set(items)
+    });
+    methods.insert(
+        "enumerate".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let pairs: Vec<Value> = array_arg("enumerate", &args, 0)
+                .borrow()
+                .iter()
+                .enumerate()
+                .map(|(i, value)| pair(Value::Number(i as f64), value.clone()))
+                .collect();
+            Value::Array(Rc::new(RefCell::new(pairs.into())))
+        },
+    );
+    methods.insert("zip".to_string(), |_this: &Value, args: Vec<Value>| {
+        let a = array_arg("zip", &args, 0).borrow();
+        let b = array_arg("zip", &args, 1).borrow();
+        let pairs: Vec<Value> = a
+            .iter()
+            .zip(b.iter())
+            .map(|(a, b)| pair(a.clone(), b.clone()))
+            .collect();
+        Value::Array(Rc::new(RefCell::new(pairs.into())))
+    });
+    methods.insert("system".to_string(), |_this: &Value, args: Vec<Value>| {
+        let command = string_argument("system", &args, 0);
+        let mut shell = if cfg!(windows) {
+            let mut shell = std::process::Command::new("cmd");
+            shell.args(["/C", command]);
+            shell
         } else {
+            let mut shell = std::process::Command::new("sh");
+            shell.args(["-c", command]);
+            shell
+        };
+        run_process("system", command, &mut shell)
+    });
+    methods.insert("spawn".to_string(), |_this: &Value, args: Vec<Value>| {
+        spawn_process("spawn", &args)
+    });
+    methods.insert("exec".to_string(), |_this: &Value, args: Vec<Value>| {
+        spawn_process("exec", &args)
+    });
+    methods.insert(
+        "base64_encode".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            Value::String(base64_encode(
+                string_argument("base64_encode", &args, 0).as_bytes(),
+            ))
+        },
+    );
+    methods.insert(
+        "base64_decode".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let encoded = string_argument("base64_decode", &args, 0);
+            let bytes = base64_decode(encoded).unwrap_or_else(|| {
+                runtime_error(format!("base64_decode() got invalid base64: {:?}", encoded).as_str())
+            });
+            decoded_string("base64_decode", bytes)
+        },
+    );
+    methods.insert(
+        "hex_encode".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            Value::String(hex_encode(
+                string_argument("hex_encode", &args, 0).as_bytes(),
+            ))
+        },
+    );
+    methods.insert(
+        "hex_decode".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let encoded = string_argument("hex_decode", &args, 0);
+            let bytes = hex_decode(encoded).unwrap_or_else(|| {
+                runtime_error(format!("hex_decode() got invalid hex: {:?}", encoded).as_str())
+            });
+            decoded_string("hex_decode", bytes)
+        },
+    );
+    methods.insert("hash".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(hash_value(&args[0]))
+    });
+    #[cfg(feature = "crypto")]
+    methods.insert("sha256".to_string(), |_this: &Value, args: Vec<Value>| {
+        use sha2::{Digest, Sha256};
+        let digest = Sha256::digest(string_argument("sha256", &args, 0).as_bytes());
+        Value::String(hex_encode(&digest))
+    });
+    methods.insert("exit".to_string(), |_this: &Value, args: Vec<Value>| {
+        let code = match args.first() {
+            Some(_) => number_arg("exit", &args, 0),
+            None => 0.0,
+        };
+        if code.fract() != 0.0 || !(0.0..=255.0).contains(&code) {
             runtime_error(
                 format!(
-                    "`get` method called on non-string value: expected String, got {:?}",
-                    this,
+                    "exit() code must be a whole number from 0 to 255: got {}",
+                    format_number(code)
                 )
                 .as_str(),
             )
         }
+        let _ = std::io::stdout().flush();
+        // Unwinds without running the panic hook, so nothing is printed. The binary turns this
+        // into the process exit code, embedders get it back from `TreeWalk::try_evaluate`
+        std::panic::resume_unwind(Box::new(EvalError::Exit(code as i32)))
     });
-    methods.insert("to_int".to_string(), |this: &Value, _args: Vec<Value>| {
-        if let Value::String(s) = this {
-            if let Ok(n) = s.parse::<f64>() {
-                Value::Number(n)
+    methods.insert("deep_eq".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Boolean(deep_eq(&args[0], &args[1]))
+    });
+    methods.insert("freeze".to_string(), |_this: &Value, args: Vec<Value>| {
+        let value = args.first().cloned().unwrap_or(Value::Null);
+        freeze(&value, false);
+        value
+    });
+    methods.insert(
+        "deep_freeze".to_string(),
+        |_this: &Value, args: Vec<Value>| {
+            let value = args.first().cloned().unwrap_or(Value::Null);
+            freeze(&value, true);
+            value
+        },
+    );
+    methods.insert("chr".to_string(), |_this: &Value, args: Vec<Value>| {
+        if let Value::Number(code) = args[0] {
+            let character = if code.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(&code) {
+                char::from_u32(code as u32)
             } else {
-                runtime_error(
+                None
+            };
+            match character {
+                Some(c) => Value::String(c.to_string()),
+                None => runtime_error(
                     format!(
-                        "Could not parse string to number in `to_int` method: got {:?}",
-                        s,
+                        "chr() called with invalid code point: {}",
+                        format_number(code)
                     )
                     .as_str(),
-                )
+                ),
             }
         } else {
+            runtime_error(format!("chr() argument must be a number: got {:?}", args[0]).as_str())
+        }
+    });
+    methods.insert("range".to_string(), |_this: &Value, args: Vec<Value>| {
+        let numbers: Vec<f64> = args
+            .iter()
+            .map(|arg| match arg {
+                Value::Number(n) => *n,
+                other => runtime_error(
+                    format!("range() arguments must be numbers: got {:?}", other).as_str(),
+                ),
+            })
+            .collect();
+        let (start, end, step) = match numbers[..] {
+            [end] => (0.0, end, 1.0),
+            [start, end] => (start, end, 1.0),
+            [start, end, step] => (start, end, step),
+            _ => unreachable!("arity is checked before the call"),
+        };
+        if step == 0.0 || !step.is_finite() {
+            runtime_error("range() step must be a non-zero number");
+        }
+        let length = ((end - start) / step).ceil().max(0.0);
+        if length > MAX_RANGE_LENGTH as f64 {
             runtime_error(
                 format!(
-                    "`to_int` method called on non-string value: expected String, got {:?}",
-                    this,
+                    "range() would create {} elements, more than the limit of {}",
+                    format_number(length),
+                    MAX_RANGE_LENGTH,
                 )
                 .as_str(),
-            )
+            );
         }
+        let items: Vec<Value> = (0..length as usize)
+            .map(|i| Value::Number(start + i as f64 * step))
+            .collect();
+        Value::Array(Rc::new(RefCell::new(items.into())))
     });
-    methods.insert("to_float".to_string(), |this: &Value, _args: Vec<Value>| {
-        if let Value::String(s) = this {
-            if let Ok(n) = s.parse::<f64>() {
-                Value::Number(n)
+    methods.insert("fill".to_string(), |_this: &Value, args: Vec<Value>| {
+        let count = array_length_arg("fill", &args, 1);
+        Value::Array(Rc::new(RefCell::new(vec![args[0].clone(); count].into())))
+    });
+    methods.insert("assert".to_string(), |_this: &Value, args: Vec<Value>| {
+        if !args[0].is_truthy() {
+            match args.get(1) {
+                Some(message) => runtime_error(format!("Assertion failed: {}", message).as_str()),
+                None => runtime_error("Assertion failed"),
+            }
+        }
+        Value::Null
+    });
+    #[cfg(feature = "regex")]
+    methods.extend(crate::treewalk::patterns::regex_methods());
+    methods
+}
+
+pub fn std_arities() -> HashMap<String, Arity> {
+    #[cfg_attr(not(any(feature = "regex", feature = "crypto")), allow(unused_mut))]
+    let mut table = arities(&[
+        ("time", 0, Some(0)),
+        ("time_millis", 0, Some(0)),
+        ("clock", 0, Some(0)),
+        ("now", 0, Some(0)),
+        ("utcnow", 0, Some(0)),
+        ("format_time", 2, Some(3)),
+        ("random", 0, Some(0)),
+        ("random_int", 2, Some(2)),
+        ("choice", 1, Some(1)),
+        ("shuffle", 1, Some(1)),
+        ("seed", 1, Some(1)),
+        ("print", 0, None),
+        ("println", 0, None),
+        ("argv", 0, Some(0)),
+        ("env_get", 1, Some(1)),
+        ("env_set", 2, Some(2)),
+        ("env_vars", 0, Some(0)),
+        ("get_line", 0, Some(0)),
+        ("input", 0, Some(1)),
+        ("write_file", 2, Some(2)),
+        ("read_file", 1, Some(1)),
+        ("append_file", 2, Some(2)),
+        ("file_exists", 1, Some(1)),
+        ("delete_file", 1, Some(1)),
+        ("list_dir", 1, Some(1)),
+        ("mkdir", 1, Some(1)),
+        ("sleep", 1, Some(1)),
+        ("default", 2, Some(2)),
+        ("map", 0, Some(1)),
+        ("set", 0, Some(1)),
+        ("enumerate", 1, Some(1)),
+        ("zip", 2, Some(2)),
+        ("system", 1, Some(1)),
+        ("spawn", 1, Some(2)),
+        ("exec", 2, Some(2)),
+        ("base64_encode", 1, Some(1)),
+        ("base64_decode", 1, Some(1)),
+        ("hex_encode", 1, Some(1)),
+        ("hex_decode", 1, Some(1)),
+        ("hash", 1, Some(1)),
+        ("exit", 0, Some(1)),
+        ("deep_eq", 2, Some(2)),
+        ("freeze", 1, Some(1)),
+        ("deep_freeze", 1, Some(1)),
+        ("chr", 1, Some(1)),
+        ("range", 1, Some(3)),
+        ("fill", 2, Some(2)),
+        ("assert", 1, Some(2)),
+    ]);
+    #[cfg(feature = "crypto")]
+    table.extend(arities(&[("sha256", 1, Some(1))]));
+    #[cfg(feature = "regex")]
+    table.extend(crate::treewalk::patterns::regex_arities());
+    table
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64_encode(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let group = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |group, (i, &b)| group | (b as u32) << (16 - 8 * i));
+        // A chunk of n bytes fills n + 1 characters, the rest is padding
+        for i in 0..4 {
+            if i <= chunk.len() {
+                let index = (group >> (18 - 6 * i)) & 0x3f;
+                encoded.push(BASE64_ALPHABET[index as usize] as char);
             } else {
-                runtime_error(
-                    format!(
-                        "Could not parse string to number in `to_float` method: got {:?}",
-                        s,
-                    )
-                    .as_str(),
-                )
+                encoded.push('=');
             }
-        } else {
-            runtime_error(
-                format!(
-                    "`to_float` method called on non-string value: expected String, got {:?}",
-                    this,
-                )
-                .as_str(),
-            )
         }
+    }
+    encoded
+}
+
+// Padding is optional, but if present must complete the last group of four
+fn base64_decode(encoded: &str) -> Option<Vec<u8>> {
+    let data = encoded.trim_end_matches('=');
+    let padding = encoded.len() - data.len();
+    if padding > 2 || (padding > 0 && !encoded.len().is_multiple_of(4)) || data.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(data.len() * 3 / 4);
+    for chunk in data.as_bytes().chunks(4) {
+        let mut group = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = BASE64_ALPHABET.iter().position(|&a| a == c)? as u32;
+            group |= value << (18 - 6 * i);
+        }
+        for i in 0..chunk.len() - 1 {
+            bytes.push((group >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+fn hex_decode(encoded: &str) -> Option<Vec<u8>> {
+    // from_str_radix alone would also accept a leading '+'
+    if !encoded.len().is_multiple_of(2) || !encoded.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..encoded.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&encoded[i..i + 2], 16).ok())
+        .collect()
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf29ce484222325;
+const FNV_PRIME: u64 = 0x100000001b3;
+
+// Hashes the canonical form of the value. The result is cut to 53 bits so it is a whole number
+// that a Number holds exactly
+fn hash_value(value: &Value) -> f64 {
+    let mut canonical = String::new();
+    write_canonical(value, &mut canonical);
+    let hash = canonical.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
     });
-    methods.insert("replace".to_string(), |this: &Value, _args: Vec<Value>| {
-        if let Value::String(s) = this {
+    (hash & ((1 << 53) - 1)) as f64
+}
+
+// Writes a form of the value that is the same for deep-equal values. Strings are quoted so
+// "1" and 1 differ, and entries are sorted since key order doesn't affect equality
+fn write_canonical(value: &Value, out: &mut String) {
+    let canonical_entries = |entries: Vec<(String, &Value)>, out: &mut String| {
+        let mut entries: Vec<String> = entries
+            .into_iter()
+            .map(|(key, value)| {
+                let mut entry = key + ":";
+                write_canonical(value, &mut entry);
+                entry
+            })
+            .collect();
+        entries.sort();
+        out.push_str(&entries.join(","));
+    };
+    match value {
+        Value::Number(n) => out.push_str(&format_number(*n)),
+        Value::Boolean(b) => out.push_str(&b.to_string()),
+        Value::String(s) => out.push_str(&format!("{:?}", s)),
+        Value::Null => out.push_str("null"),
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.borrow().iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(properties) => {
+            let properties = properties.borrow();
+            out.push('{');
+            canonical_entries(
+                properties
+                    .iter()
+                    .map(|(key, value)| (format!("{:?}", key), value))
+                    .collect(),
+                out,
+            );
+            out.push('}');
+        }
+        Value::Map(entries) => {
+            let entries = entries.borrow();
+            out.push_str("Map{");
+            canonical_entries(
+                entries
+                    .iter()
+                    .map(|(key, value)| {
+                        let mut key_form = String::new();
+                        write_canonical(&key.to_value(), &mut key_form);
+                        (key_form, value)
+                    })
+                    .collect(),
+                out,
+            );
+            out.push('}');
+        }
+        Value::Set(items) => {
+            let mut items: Vec<String> = items
+                .borrow()
+                .iter()
+                .map(|item| {
+                    let mut item_form = String::new();
+                    write_canonical(item, &mut item_form);
+                    item_form
+                })
+                .collect();
+            items.sort();
+            out.push_str("Set{");
+            out.push_str(&items.join(","));
+            out.push('}');
+        }
+        _ => runtime_error(format!("Cannot hash a value of type {}", value.type_name()).as_str()),
+    }
+}
+
+fn decoded_string(name: &str, bytes: Vec<u8>) -> Value {
+    match String::from_utf8(bytes) {
+        Ok(s) => Value::String(s),
+        Err(_) => {
+            runtime_error(format!("{}() decoded bytes that aren't valid UTF-8", name).as_str())
+        }
+    }
+}
+
+// Runs args[0] directly with the optional array of string arguments in args[1]
+fn spawn_process(name: &str, args: &[Value]) -> Value {
+    let program = string_argument(name, args, 0);
+    let mut process = std::process::Command::new(program);
+    if args.len() > 1 {
+        let arguments = array_arg(name, args, 1).borrow();
+        for i in 0..arguments.len() {
+            process.arg(string_argument(name, &arguments, i));
+        }
+    }
+    run_process(name, program, &mut process)
+}
+
+// Waits for the process and collects its output. The code is null if it was ended by a signal
+fn run_process(name: &str, command: &str, process: &mut std::process::Command) -> Value {
+    let output = process.output().unwrap_or_else(|e| {
+        runtime_error(format!("{}() could not run '{}': {}", name, command, e).as_str())
+    });
+    let fields: ObjectMap = [
+        (
+            "stdout",
+            Value::String(String::from_utf8_lossy(&output.stdout).into_owned()),
+        ),
+        (
+            "stderr",
+            Value::String(String::from_utf8_lossy(&output.stderr).into_owned()),
+        ),
+        (
+            "code",
+            output
+                .status
+                .code()
+                .map_or(Value::Null, |code| Value::Number(code as f64)),
+        ),
+    ]
+    .into_iter()
+    .map(|(field, value)| (field.to_string(), value))
+    .collect();
+    Value::Object(Rc::new(RefCell::new(fields.into())))
+}
+
+fn io_error(name: &str, path: &str, error: std::io::Error) -> ! {
+    runtime_error(format!("{}() failed for '{}': {}", name, path, error).as_str())
+}
+
+pub fn math_methods() -> HashMap<String, StdMethod> {
+    // For the 'math' object nested in 'std', E.G. std.math.sqrt(2)
+
+    /*
+    Description of the methods:
+    - sqrt: Returns the square root of a number, NaN for negative numbers.
+    - abs: Returns the absolute value of a number.
+    - pow: Raises the first argument to the power of the second.
+    - sin, cos, tan: Trigonometric functions, in radians.
+    - atan2: Returns the angle of the point (x, y) given as atan2(y, x), in radians.
+    - log: Returns the natural logarithm of a number, NaN for negative numbers.
+    - log10: Returns the base-10 logarithm of a number, NaN for negative numbers.
+    - exp: Returns e raised to the given power.
+    - min: Returns the smallest of two numbers, or of the numbers in an array.
+    - max: Returns the largest of two numbers, or of the numbers in an array.
+    - clamp: Limits the first argument to the range given by the second and third.
+    Constants: pi, e
+    */
+
+    let mut methods: HashMap<String, StdMethod> = HashMap::new();
+    methods.insert("sqrt".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("sqrt", &args, 0).sqrt())
+    });
+    methods.insert("abs".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("abs", &args, 0).abs())
+    });
+    methods.insert("pow".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("pow", &args, 0).powf(number_arg("pow", &args, 1)))
+    });
+    methods.insert("sin".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("sin", &args, 0).sin())
+    });
+    methods.insert("cos".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("cos", &args, 0).cos())
+    });
+    methods.insert("tan".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("tan", &args, 0).tan())
+    });
+    methods.insert("atan2".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("atan2", &args, 0).atan2(number_arg("atan2", &args, 1)))
+    });
+    methods.insert("log".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("log", &args, 0).ln())
+    });
+    methods.insert("log10".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("log10", &args, 0).log10())
+    });
+    methods.insert("exp".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(number_arg("exp", &args, 0).exp())
+    });
+    methods.insert("min".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(
+            numbers_from_args("min", &args)
+                .into_iter()
+                .fold(f64::INFINITY, f64::min),
+        )
+    });
+    methods.insert("max".to_string(), |_this: &Value, args: Vec<Value>| {
+        Value::Number(
+            numbers_from_args("max", &args)
+                .into_iter()
+                .fold(f64::NEG_INFINITY, f64::max),
+        )
+    });
+    methods.insert("clamp".to_string(), |_this: &Value, args: Vec<Value>| {
+        let value = number_arg("clamp", &args, 0);
+        let (low, high) = (number_arg("clamp", &args, 1), number_arg("clamp", &args, 2));
+        if low > high {
+            runtime_error(
+                format!(
+                    "`clamp` lower bound must not exceed the upper bound: got {} and {}",
+                    format_number(low),
+                    format_number(high),
+                )
+                .as_str(),
+            )
+        }
+        Value::Number(value.clamp(low, high))
+    });
+    methods
+}
+
+pub fn math_constants() -> HashMap<String, f64> {
+    HashMap::from([
+        ("pi".to_string(), std::f64::consts::PI),
+        ("e".to_string(), std::f64::consts::E),
+    ])
+}
+
+pub fn math_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("sqrt", 1, Some(1)),
+        ("abs", 1, Some(1)),
+        ("pow", 2, Some(2)),
+        ("sin", 1, Some(1)),
+        ("cos", 1, Some(1)),
+        ("tan", 1, Some(1)),
+        ("atan2", 2, Some(2)),
+        ("log", 1, Some(1)),
+        ("log10", 1, Some(1)),
+        ("exp", 1, Some(1)),
+        ("min", 1, Some(2)),
+        ("max", 1, Some(2)),
+        ("clamp", 3, Some(3)),
+    ])
+}
+
+fn number_arg(name: &str, args: &[Value], index: usize) -> f64 {
+    match &args[index] {
+        Value::Number(n) => *n,
+        other => runtime_error(
+            format!(
+                "`{}` argument {} must be a number: got {}",
+                name,
+                index + 1,
+                other.type_name(),
+            )
+            .as_str(),
+        ),
+    }
+}
+
+// Reads an array length, which must be a whole number from 0 up to MAX_RANGE_LENGTH
+fn array_length_arg(name: &str, args: &[Value], index: usize) -> usize {
+    let length = number_arg(name, args, index);
+    if length < 0.0 || length.fract() != 0.0 {
+        runtime_error(
+            format!(
+                "`{}` length must be a non-negative whole number: got {}",
+                name,
+                format_number(length),
+            )
+            .as_str(),
+        );
+    }
+    if length > MAX_RANGE_LENGTH as f64 {
+        runtime_error(
+            format!(
+                "`{}` would create {} elements, more than the limit of {}",
+                name,
+                format_number(length),
+                MAX_RANGE_LENGTH,
+            )
+            .as_str(),
+        );
+    }
+    length as usize
+}
+
+fn pair(a: Value, b: Value) -> Value {
+    Value::Array(Rc::new(RefCell::new(vec![a, b].into())))
+}
+
+fn array_arg<'a>(
+    name: &str,
+    args: &'a [Value],
+    index: usize,
+) -> &'a Rc<RefCell<Freezable<Vec<Value>>>> {
+    match &args[index] {
+        Value::Array(values) => values,
+        other => runtime_error(
+            format!(
+                "`{}` argument {} must be an array: got {}",
+                name,
+                index + 1,
+                other.type_name(),
+            )
+            .as_str(),
+        ),
+    }
+}
+
+// `min` and `max` take either two numbers or a single non-empty array of numbers
+fn numbers_from_args(name: &str, args: &[Value]) -> Vec<f64> {
+    if let [Value::Array(values)] = args {
+        let values = values.borrow();
+        if values.is_empty() {
+            runtime_error(format!("`{}` called on an empty array", name).as_str());
+        }
+        return (0..values.len())
+            .map(|i| number_arg(name, &values, i))
+            .collect();
+    }
+    if args.len() != 2 {
+        runtime_error(format!("`{}` expects two numbers or a single array", name).as_str());
+    }
+    vec![number_arg(name, args, 0), number_arg(name, args, 1)]
+}
+
+pub fn string_methods() -> HashMap<String, StdMethod> {
+    let mut methods: HashMap<String, StdMethod> = HashMap::new();
+
+    /*
+    Description of the methods:
+    - length: Returns the length of the string.
+    - ord: Returns the code point of a one-character string, or of the character at the given index.
+    - get: Returns the character at the given index.
+    - to_int: Parses the string as an integer in the given radix (2 to 36, default 10). A 0x, 0o
+      or 0b prefix selects the radix when none is given.
+    - to_float: Parses the string as a finite decimal number.
+    - is_number: Returns whether to_float would succeed.
+    - parse_int: Same as to_int.
+    The parsing methods ignore surrounding whitespace and return null when the string isn't a valid
+    number, so input can be validated without raising an error.
+    - replace: Replaces all occurrences of the first argument with the second argument.
+    - split: Splits the string by the given separator, or on runs of whitespace without one. An
+      empty separator splits into characters. The optional second argument caps the number of
+      pieces, the last piece keeping the rest of the string.
+    - chars: Returns an array of the characters in the string.
+    - bytes: Returns an array of the UTF-8 bytes of the string as numbers.
+    - lines: Splits the string on "\n" or "\r\n", without a trailing empty line.
+    - find: Returns the index of the first occurrence of the given string.
+    - contains: Returns whether the given string occurs in the string.
+    - starts_with: Returns whether the string begins with the given string.
+    - ends_with: Returns whether the string ends with the given string.
+    - slice: Returns the characters from start up to (not including) end, clamped to the string.
+    - substring: Same as slice.
+    - reverse: Returns a new string with the characters in reverse order.
+    - hash: Returns the same hash as std.hash does for the string.
+    - upper: Returns the string converted to uppercase.
+    - lower: Returns the string converted to lowercase.
+    - capitalize: Returns the string with its first character uppercased.
+    - eq_ignore_case: Returns whether the string equals the given string, ignoring case.
+    - trim: Removes whitespace, or any of the characters in the given string, from both ends.
+    - trim_start: Like trim, but only from the start of the string.
+    - trim_end: Like trim, but only from the end of the string.
+    */
+
+    methods.insert("length".to_string(), |this: &Value, _args: Vec<Value>| {
+        if let Value::String(s) = this {
+            Value::Number(s.len() as f64)
+        } else {
+            runtime_error(
+                format!(
+                    "`length` method called on non-string value: expected String, got {:?}",
+                    this,
+                )
+                .as_str(),
+            )
+        }
+    });
+    methods.insert("ord".to_string(), |this: &Value, args: Vec<Value>| {
+        let s = string_receiver("ord", this);
+        let c = match args.first() {
+            // negative indices count from the end
+            Some(_) => {
+                let i = number_arg("ord", &args, 0);
+                let length = s.chars().count();
+                match resolve_index(i, length) {
+                    Some(index) => s.chars().nth(index).unwrap(),
+                    None => runtime_error(
+                        format!(
+                            "Index out of bounds in `ord` method: index {}, length {}",
+                            format_number(i),
+                            length
+                        )
+                        .as_str(),
+                    ),
+                }
+            }
+            None => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => c,
+                    _ => runtime_error("ord() called on string with length != 1"),
+                }
+            }
+        };
+        Value::Number(c as u32 as f64)
+    });
+    methods.insert("get".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::String(s) = this {
+            if let Value::Number(i) = args[0] {
+                // negative indices count from the end
+                let length = s.chars().count();
+                if let Some(index) = resolve_index(i, length) {
+                    Value::String(s.chars().nth(index).unwrap().to_string())
+                } else {
+                    runtime_error(
+                        format!(
+                            "Index out of bounds in `get` method: index {}, length {}",
+                            i, length,
+                        )
+                        .as_str(),
+                    )
+                }
+            } else {
+                runtime_error(
+                    format!("Index must be a number in `get` method: got {:?}", args[0]).as_str(),
+                )
+            }
+        } else {
+            runtime_error(
+                format!(
+                    "`get` method called on non-string value: expected String, got {:?}",
+                    this,
+                )
+                .as_str(),
+            )
+        }
+    });
+    methods.insert("to_int".to_string(), |this: &Value, args: Vec<Value>| {
+        let s = string_receiver("to_int", this);
+        let radix = radix_arg("to_int", &args);
+        parse_integer(s, radix).map_or(Value::Null, Value::Number)
+    });
+    methods.insert("to_float".to_string(), |this: &Value, _args: Vec<Value>| {
+        parse_float(string_receiver("to_float", this)).map_or(Value::Null, Value::Number)
+    });
+    methods.insert(
+        "is_number".to_string(),
+        |this: &Value, _args: Vec<Value>| {
+            Value::Boolean(parse_float(string_receiver("is_number", this)).is_some())
+        },
+    );
+    methods.insert("parse_int".to_string(), |this: &Value, args: Vec<Value>| {
+        let s = string_receiver("parse_int", this);
+        let radix = radix_arg("parse_int", &args);
+        parse_integer(s, radix).map_or(Value::Null, Value::Number)
+    });
+    methods.insert("replace".to_string(), |this: &Value, _args: Vec<Value>| {
+        if let Value::String(s) = this {
             let mut s = s.clone();
             for i in 0.._args.len() / 2 {
                 if let Value::String(a) = &_args[i * 2] {
                     if let Value::String(b) = &_args[i * 2 + 1] {
                         s = s.replace(a, b);
                     } else {
-                        return runtime_error(
+                        runtime_error(
                             format!(
                                 "replace arguments must be strings: got {:?}",
                                 _args[i * 2 + 1],
@@ -258,7 +1324,7 @@ pub fn string_methods() -> HashMap<String, StdMethod> {
                         );
                     }
                 } else {
-                    return runtime_error(
+                    runtime_error(
                         format!("replace arguments must be strings: got {:?}", _args[i * 2],)
                             .as_str(),
                     );
@@ -276,32 +1342,69 @@ pub fn string_methods() -> HashMap<String, StdMethod> {
         }
     });
     methods.insert("split".to_string(), |this: &Value, args: Vec<Value>| {
-        if let Value::String(s) = this {
-            if let Value::String(sep) = args.first().unwrap_or(&Value::String(" ".to_string())) {
-                let parts: Vec<Value> =
-                    s.split(sep).map(|s| Value::String(s.to_string())).collect();
-                Value::Array(Rc::new(RefCell::new(parts)))
-            } else {
-                runtime_error(
-                    format!("split argument must be a string: got {:?}", args.first()).as_str(),
-                )
-            }
-        } else {
-            runtime_error(
+        let s = string_receiver("split", this);
+        let limit = match args.get(1) {
+            None => usize::MAX,
+            Some(Value::Number(n)) if *n >= 1.0 && n.fract() == 0.0 => *n as usize,
+            Some(other) => runtime_error(
                 format!(
-                    "`split` method called on non-string value: expected String, got {:?}",
-                    this,
+                    "split limit must be a whole number of at least 1: got {:?}",
+                    other
                 )
                 .as_str(),
-            )
-        }
+            ),
+        };
+        let parts: Vec<&str> = match args.first() {
+            None | Some(Value::Null) => split_whitespace(s, limit),
+            Some(Value::String(sep)) if sep.is_empty() => {
+                let mut parts: Vec<&str> = Vec::new();
+                for (i, (start, c)) in s.char_indices().enumerate() {
+                    if i + 1 == limit {
+                        parts.push(&s[start..]);
+                        break;
+                    }
+                    parts.push(&s[start..start + c.len_utf8()]);
+                }
+                parts
+            }
+            Some(Value::String(sep)) => s.splitn(limit, sep.as_str()).collect(),
+            Some(other) => {
+                runtime_error(format!("split argument must be a string: got {:?}", other).as_str())
+            }
+        };
+        let parts: Vec<Value> = parts
+            .into_iter()
+            .map(|part| Value::String(part.to_string()))
+            .collect();
+        Value::Array(Rc::new(RefCell::new(parts.into())))
+    });
+    methods.insert("chars".to_string(), |this: &Value, _args: Vec<Value>| {
+        let chars: Vec<Value> = string_receiver("chars", this)
+            .chars()
+            .map(|c| Value::String(c.to_string()))
+            .collect();
+        Value::Array(Rc::new(RefCell::new(chars.into())))
+    });
+    methods.insert("bytes".to_string(), |this: &Value, _args: Vec<Value>| {
+        let bytes: Vec<Value> = string_receiver("bytes", this)
+            .bytes()
+            .map(|b| Value::Number(b as f64))
+            .collect();
+        Value::Array(Rc::new(RefCell::new(bytes.into())))
+    });
+    methods.insert("lines".to_string(), |this: &Value, _args: Vec<Value>| {
+        let lines: Vec<Value> = string_receiver("lines", this)
+            .lines()
+            .map(|line| Value::String(line.to_string()))
+            .collect();
+        Value::Array(Rc::new(RefCell::new(lines.into())))
     });
     methods.insert("find".to_string(), |this: &Value, args: Vec<Value>| {
         if let Value::String(s) = this {
             if let Some(i) = s.find(if let Value::String(s) = &args[0] {
                 s
             } else {
-                return runtime_error(
+                runtime_error(
                     format!(
                         "`find` method called with non-string argument: expected String, got {:?}",
                         args[0]
@@ -323,36 +1426,313 @@ pub fn string_methods() -> HashMap<String, StdMethod> {
             )
         }
     });
+    methods.insert("contains".to_string(), |this: &Value, args: Vec<Value>| {
+        let s = string_receiver("contains", this);
+        let needle = string_argument("contains", &args, 0);
+        // A single character is searched for as a char, which avoids the substring search setup
+        let mut chars = needle.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Value::Boolean(s.contains(c)),
+            _ => Value::Boolean(s.contains(needle)),
+        }
+    });
+    methods.insert(
+        "starts_with".to_string(),
+        |this: &Value, args: Vec<Value>| {
+            let s = string_receiver("starts_with", this);
+            Value::Boolean(s.starts_with(string_argument("starts_with", &args, 0)))
+        },
+    );
+    methods.insert("ends_with".to_string(), |this: &Value, args: Vec<Value>| {
+        let s = string_receiver("ends_with", this);
+        Value::Boolean(s.ends_with(string_argument("ends_with", &args, 0)))
+    });
+    methods.insert("slice".to_string(), |this: &Value, args: Vec<Value>| {
+        slice_string("slice", this, &args)
+    });
+    methods.insert("substring".to_string(), |this: &Value, args: Vec<Value>| {
+        slice_string("substring", this, &args)
+    });
+    methods.insert("reverse".to_string(), |this: &Value, _args: Vec<Value>| {
+        Value::String(string_receiver("reverse", this).chars().rev().collect())
+    });
+    methods.insert("hash".to_string(), |this: &Value, _args: Vec<Value>| {
+        string_receiver("hash", this);
+        Value::Number(hash_value(this))
+    });
+    methods.insert("upper".to_string(), |this: &Value, _args: Vec<Value>| {
+        Value::String(string_receiver("upper", this).to_uppercase())
+    });
+    methods.insert("lower".to_string(), |this: &Value, _args: Vec<Value>| {
+        Value::String(string_receiver("lower", this).to_lowercase())
+    });
+    methods.insert(
+        "capitalize".to_string(),
+        |this: &Value, _args: Vec<Value>| {
+            let s = string_receiver("capitalize", this);
+            let mut chars = s.chars();
+            match chars.next() {
+                Some(first) => Value::String(first.to_uppercase().chain(chars).collect()),
+                None => Value::String(String::new()),
+            }
+        },
+    );
+    methods.insert(
+        "eq_ignore_case".to_string(),
+        |this: &Value, args: Vec<Value>| {
+            let s = string_receiver("eq_ignore_case", this);
+            if let Value::String(other) = &args[0] {
+                // Uppercasing first folds characters like ß, whose uppercase form is SS
+                let fold = |s: &str| s.to_uppercase().to_lowercase();
+                Value::Boolean(fold(s) == fold(other))
+            } else {
+                runtime_error(
+                    format!(
+                        "Argument must be a string in `eq_ignore_case` method: got {:?}",
+                        args[0]
+                    )
+                    .as_str(),
+                )
+            }
+        },
+    );
+    methods.insert("trim".to_string(), |this: &Value, args: Vec<Value>| {
+        trim_string("trim", this, &args, true, true)
+    });
+    methods.insert(
+        "trim_start".to_string(),
+        |this: &Value, args: Vec<Value>| trim_string("trim_start", this, &args, true, false),
+    );
+    methods.insert("trim_end".to_string(), |this: &Value, args: Vec<Value>| {
+        trim_string("trim_end", this, &args, false, true)
+    });
     methods
 }
 
+// Like resolve_index, but out of range indices are clamped to `0..=length` instead of rejected
+fn clamp_index(index: f64, length: usize) -> usize {
+    let index = index as i64;
+    let index = if index < 0 {
+        length as i64 + index
+    } else {
+        index
+    };
+    index.clamp(0, length as i64) as usize
+}
+
+fn string_receiver<'a>(name: &str, this: &'a Value) -> &'a str {
+    if let Value::String(s) = this {
+        s
+    } else {
+        runtime_error(
+            format!(
+                "`{}` method called on non-string value: expected String, got {:?}",
+                name, this,
+            )
+            .as_str(),
+        )
+    }
+}
+
+// Shared by slice and substring. Indices count characters, negative ones from the end.
+fn slice_string(name: &str, this: &Value, args: &[Value]) -> Value {
+    let s = string_receiver(name, this);
+    let length = s.chars().count();
+    let bound = |arg: Option<&Value>, default: usize| match arg {
+        None => default,
+        Some(Value::Number(i)) => clamp_index(*i, length),
+        Some(other) => runtime_error(
+            format!(
+                "Index must be a number in `{}` method: got {:?}",
+                name, other
+            )
+            .as_str(),
+        ),
+    };
+    let start = bound(args.first(), 0);
+    let end = bound(args.get(1), length);
+    if start >= end {
+        return Value::String(String::new());
+    }
+    Value::String(s.chars().skip(start).take(end - start).collect())
+}
+
+// Splits on runs of whitespace, ignoring leading and trailing whitespace. Once `limit - 1` pieces
+// are found, the rest of the string (minus its leading whitespace) becomes the last piece.
+fn split_whitespace(s: &str, limit: usize) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut rest = s.trim_start();
+    while !rest.is_empty() {
+        if parts.len() + 1 == limit {
+            parts.push(rest);
+            break;
+        }
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        parts.push(&rest[..end]);
+        rest = rest[end..].trim_start();
+    }
+    parts
+}
+
+fn string_argument<'a>(name: &str, args: &'a [Value], index: usize) -> &'a str {
+    if let Value::String(s) = &args[index] {
+        s
+    } else {
+        runtime_error(
+            format!(
+                "`{}` method called with non-string argument: expected String, got {:?}",
+                name, args[index],
+            )
+            .as_str(),
+        )
+    }
+}
+
+fn radix_arg(name: &str, args: &[Value]) -> Option<u32> {
+    match args.first() {
+        None => None,
+        Some(Value::Number(r)) if r.fract() == 0.0 && (2.0..=36.0).contains(r) => Some(*r as u32),
+        Some(other) => runtime_error(
+            format!(
+                "Radix must be a whole number from 2 to 36 in `{}` method: got {}",
+                name, other,
+            )
+            .as_str(),
+        ),
+    }
+}
+
+// Without a radix, a 0x, 0o or 0b prefix picks one. With a radix, only its own prefix is allowed
+fn parse_integer(s: &str, radix: Option<u32>) -> Option<f64> {
+    let s = s.trim();
+    let (negative, s) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let prefixed = [
+        ("0x", 16),
+        ("0X", 16),
+        ("0o", 8),
+        ("0O", 8),
+        ("0b", 2),
+        ("0B", 2),
+    ]
+    .into_iter()
+    .find(|(prefix, base)| s.starts_with(prefix) && radix.unwrap_or(*base) == *base);
+    let (digits, radix) = match prefixed {
+        Some((prefix, base)) => (&s[prefix.len()..], base),
+        None => (s, radix.unwrap_or(10)),
+    };
+    // from_str_radix would accept a second sign
+    if digits.starts_with(['+', '-']) {
+        return None;
+    }
+    let n = u64::from_str_radix(digits, radix).ok()? as f64;
+    Some(if negative { -n } else { n })
+}
+
+fn parse_float(s: &str) -> Option<f64> {
+    s.trim().parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+// Shared by trim, trim_start and trim_end. Without an argument Unicode whitespace is stripped,
+// otherwise every character of the argument is.
+fn trim_string(name: &str, this: &Value, args: &[Value], start: bool, end: bool) -> Value {
+    let s = string_receiver(name, this);
+    let strip: Box<dyn Fn(char) -> bool> = match args.first() {
+        None => Box::new(char::is_whitespace),
+        Some(Value::String(chars)) => {
+            let chars = chars.clone();
+            Box::new(move |c| chars.contains(c))
+        }
+        Some(other) => runtime_error(
+            format!(
+                "Characters to strip must be a string in `{}` method: got {:?}",
+                name, other,
+            )
+            .as_str(),
+        ),
+    };
+    let mut trimmed = s;
+    if start {
+        trimmed = trimmed.trim_start_matches(&*strip);
+    }
+    if end {
+        trimmed = trimmed.trim_end_matches(&*strip);
+    }
+    Value::String(trimmed.to_string())
+}
+
+pub fn string_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("length", 0, Some(0)),
+        ("ord", 0, Some(1)),
+        ("get", 1, Some(1)),
+        ("to_int", 0, Some(1)),
+        ("to_float", 0, Some(0)),
+        ("is_number", 0, Some(0)),
+        ("parse_int", 0, Some(1)),
+        ("replace", 2, None),
+        ("split", 0, Some(2)),
+        ("chars", 0, Some(0)),
+        ("bytes", 0, Some(0)),
+        ("lines", 0, Some(0)),
+        ("find", 1, Some(1)),
+        ("contains", 1, Some(1)),
+        ("starts_with", 1, Some(1)),
+        ("ends_with", 1, Some(1)),
+        ("slice", 1, Some(2)),
+        ("substring", 1, Some(2)),
+        ("reverse", 0, Some(0)),
+        ("hash", 0, Some(0)),
+        ("upper", 0, Some(0)),
+        ("lower", 0, Some(0)),
+        ("capitalize", 0, Some(0)),
+        ("eq_ignore_case", 1, Some(1)),
+        ("trim", 0, Some(1)),
+        ("trim_start", 0, Some(1)),
+        ("trim_end", 0, Some(1)),
+    ])
+}
+
+const MAX_FIXED_DIGITS: f64 = 100.0;
+
 pub fn number_methods() -> HashMap<String, StdMethod> {
     let mut methods: HashMap<String, StdMethod> = HashMap::new();
 
     /*
     Description of the methods:
-    - to_string: Converts the number to a string.
+    - to_string: Converts the number to a string. With a radix of 2, 8 or 16, an integer is
+      written in that base instead.
     - round: Rounds the number to the nearest integer.
     - floor: Rounds the number down to the nearest integer.
     - ceil: Rounds the number up to the nearest integer.
+    - factorial: Returns n! for a non-negative integer n. Exact up to 22!, approximate beyond that,
+      and inf above 170!.
+    - cbrt: Returns the cube root of the number.
+    - nth_root: Returns the nth root of the number. Odd roots of negative numbers are negative.
+    - to_fixed: Formats the number as a string with the given number of decimal places (0 to 100),
+      rounding halves away from zero.
+    - to_precision: Formats the number with the given number of significant digits (1 to 100),
+      switching to exponential notation for very large or small numbers.
      */
 
-    methods.insert(
-        "to_string".to_string(),
-        |this: &Value, _args: Vec<Value>| {
-            if let Value::Number(n) = this {
-                Value::String(n.to_string())
-            } else {
-                runtime_error(
-                    format!(
-                        "`to_string` method called on non-number value: expected Number, got {:?}",
-                        this,
-                    )
-                    .as_str(),
-                )
+    methods.insert("to_string".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::Number(n) = this {
+            match args.first() {
+                Some(_) => Value::String(to_radix_string(*n, number_arg("to_string", &args, 0))),
+                None => Value::String(format_number(*n)),
             }
-        },
-    );
+        } else {
+            runtime_error(
+                format!(
+                    "`to_string` method called on non-number value: expected Number, got {:?}",
+                    this,
+                )
+                .as_str(),
+            )
+        }
+    });
     methods.insert("round".to_string(), |this: &Value, _args: Vec<Value>| {
         if let Value::Number(n) = this {
             Value::Number(n.round())
@@ -392,22 +1772,216 @@ pub fn number_methods() -> HashMap<String, StdMethod> {
             )
         }
     });
-    methods
-}
-
-pub fn array_methods() -> HashMap<String, StdMethod> {
-    let mut methods: HashMap<String, StdMethod> = HashMap::new();
-
-    /*
-    Description of the methods:
-    - length: Returns the length of the array.
-    - remove: Removes the element at the given index.
-    - push: Appends the given value to the end of the array.
+    methods.insert(
+        "factorial".to_string(),
+        |this: &Value, _args: Vec<Value>| {
+            if let Value::Number(n) = this {
+                if *n < 0.0 || n.fract() != 0.0 {
+                    runtime_error(
+                        format!(
+                            "`factorial` method requires a non-negative integer: got {}",
+                            format_number(*n),
+                        )
+                        .as_str(),
+                    )
+                }
+                // Past 170! the product is already inf, so stop instead of looping up to n
+                let n = n.min(171.0) as u32;
+                Value::Number((1..=n).map(f64::from).product())
+            } else {
+                runtime_error(
+                    format!(
+                        "`factorial` method called on non-number value: expected Number, got {:?}",
+                        this,
+                    )
+                    .as_str(),
+                )
+            }
+        },
+    );
+    methods.insert("cbrt".to_string(), |this: &Value, _args: Vec<Value>| {
+        if let Value::Number(n) = this {
+            Value::Number(n.cbrt())
+        } else {
+            runtime_error(
+                format!(
+                    "`cbrt` method called on non-number value: expected Number, got {:?}",
+                    this,
+                )
+                .as_str(),
+            )
+        }
+    });
+    methods.insert("nth_root".to_string(), |this: &Value, args: Vec<Value>| {
+        if let (Value::Number(x), Value::Number(n)) = (this, &args[0]) {
+            if *n == 0.0 {
+                runtime_error("`nth_root` method called with a root of 0")
+            }
+            let odd = n.fract() == 0.0 && n % 2.0 != 0.0;
+            if *x < 0.0 && odd {
+                Value::Number(-(-x).powf(1.0 / n))
+            } else {
+                Value::Number(x.powf(1.0 / n))
+            }
+        } else {
+            runtime_error(
+                format!(
+                    "`nth_root` method requires numbers: got {:?} and {:?}",
+                    this, args[0],
+                )
+                .as_str(),
+            )
+        }
+    });
+    methods.insert(
+        "to_precision".to_string(),
+        |this: &Value, args: Vec<Value>| {
+            if let Value::Number(n) = this {
+                let digits = number_arg("to_precision", &args, 0);
+                if !(1.0..=MAX_FIXED_DIGITS).contains(&digits) || digits.fract() != 0.0 {
+                    runtime_error(
+                        format!(
+                            "`to_precision` digits must be an integer between 1 and {}: got {}",
+                            MAX_FIXED_DIGITS,
+                            format_number(digits),
+                        )
+                        .as_str(),
+                    )
+                }
+                Value::String(to_precision(*n, digits as usize))
+            } else {
+                runtime_error(
+                    format!(
+                        "`to_precision` method called on non-number value: expected Number, got {:?}",
+                        this,
+                    )
+                    .as_str(),
+                )
+            }
+        },
+    );
+    methods.insert("to_fixed".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::Number(n) = this {
+            let digits = number_arg("to_fixed", &args, 0);
+            if !(0.0..=MAX_FIXED_DIGITS).contains(&digits) || digits.fract() != 0.0 {
+                runtime_error(
+                    format!(
+                        "`to_fixed` digits must be an integer between 0 and {}: got {}",
+                        MAX_FIXED_DIGITS,
+                        format_number(digits),
+                    )
+                    .as_str(),
+                )
+            }
+            let digits = digits as usize;
+            // Formatting rounds ties to even, so round halves away from zero first
+            let scale = 10f64.powi(digits as i32);
+            let rounded = (n * scale).round() / scale;
+            let n = if rounded.is_finite() { rounded } else { *n };
+            Value::String(format!("{:.*}", digits, n))
+        } else {
+            runtime_error(
+                format!(
+                    "`to_fixed` method called on non-number value: expected Number, got {:?}",
+                    this,
+                )
+                .as_str(),
+            )
+        }
+    });
+    methods
+}
+
+fn to_radix_string(n: f64, radix: f64) -> String {
+    if ![2.0, 8.0, 16.0].contains(&radix) {
+        runtime_error(
+            format!(
+                "`to_string` radix must be 2, 8 or 16: got {}",
+                format_number(radix)
+            )
+            .as_str(),
+        )
+    }
+    if n.fract() != 0.0 || n.abs() >= i64::MAX as f64 {
+        runtime_error(
+            format!(
+                "`to_string` with a radix needs an integer: got {}",
+                format_number(n)
+            )
+            .as_str(),
+        )
+    }
+    let sign = if n < 0.0 { "-" } else { "" };
+    let n = n.abs() as i64;
+    match radix as u32 {
+        2 => format!("{}{:b}", sign, n),
+        8 => format!("{}{:o}", sign, n),
+        _ => format!("{}{:x}", sign, n),
+    }
+}
+
+// Like JavaScript's toPrecision, exponential notation is used when the exponent is below -6 or
+// doesn't fit in the requested digits
+fn to_precision(n: f64, digits: usize) -> String {
+    if !n.is_finite() {
+        return format_number(n);
+    }
+    // Round in exponential form first, since rounding can carry into a new leading digit
+    let scientific = format!("{:.*e}", digits - 1, n);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i64 = exponent.parse().unwrap();
+    if exponent < -6 || exponent >= digits as i64 {
+        let sign = if exponent < 0 { "-" } else { "+" };
+        format!("{}e{}{}", mantissa, sign, exponent.abs())
+    } else {
+        format!("{:.*}", (digits as i64 - 1 - exponent) as usize, n)
+    }
+}
+
+pub fn number_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("to_string", 0, Some(1)),
+        ("round", 0, Some(0)),
+        ("floor", 0, Some(0)),
+        ("ceil", 0, Some(0)),
+        ("factorial", 0, Some(0)),
+        ("cbrt", 0, Some(0)),
+        ("nth_root", 1, Some(1)),
+        ("to_fixed", 1, Some(1)),
+        ("to_precision", 1, Some(1)),
+    ])
+}
+
+pub fn array_methods() -> HashMap<String, StdMethod> {
+    let mut methods: HashMap<String, StdMethod> = HashMap::new();
+
+    /*
+    Description of the methods:
+    - length: Returns the length of the array.
+    - remove: Removes the element at the given index.
+    - push: Appends the given value to the end of the array.
     - set: Sets the value at the given index.
     - get: Returns the value at the given index.
     - pop: Removes and returns the last element of the array.
     - find: Returns the index of the first occurrence of the given value.
+    - index_of: Same as find.
+    - last_index_of: Returns the index of the last occurrence of the given value.
+    - contains: Returns whether the given value occurs in the array.
+    - count: Returns how many elements equal the given value.
+    - unique: Returns a new array without duplicates, keeping the first occurrence of each value.
+    - reverse: Reverses the array in place and returns it.
+    - reversed: Returns a reversed copy of the array, leaving the original unchanged.
     - copy: Returns a shallow copy of the array.
+    - slice: Returns a new array with the elements from start up to (not including) end.
+    - concat: Returns a new array with the elements of this array followed by the given one's.
+    - insert: Inserts the value before the given index, which may equal the length to append.
+    - resize: Truncates the array to the given length, or extends it with the fill value (default
+      null), in place.
+    - sum: Returns the sum of the numbers in the array, 0 if it is empty.
+    - min: Returns the smallest number in a non-empty array.
+    - max: Returns the largest number in a non-empty array.
+    - avg: Returns the average of the numbers in a non-empty array.
+    - join: Converts every element to a string as print would and joins them with the separator.
     */
 
     methods.insert("length".to_string(), |this: &Value, _args: Vec<Value>| {
@@ -420,9 +1994,10 @@ pub fn array_methods() -> HashMap<String, StdMethod> {
     methods.insert("remove".to_string(), |this: &Value, args: Vec<Value>| {
         if let Value::Array(a) = this {
             if let Value::Number(i) = args[0] {
-                let i = i as usize;
-                if i < a.borrow().len() {
-                    let removed = a.borrow_mut().remove(i);
+                a.borrow().ensure_mutable();
+                let length = a.borrow().len();
+                if let Some(index) = resolve_index(i, length) {
+                    let removed = a.borrow_mut().remove(index);
                     removed
                 } else {
                     runtime_error(
@@ -449,6 +2024,7 @@ pub fn array_methods() -> HashMap<String, StdMethod> {
     });
     methods.insert("push".to_string(), |this: &Value, args: Vec<Value>| {
         if let Value::Array(a) = this {
+            a.borrow().ensure_mutable();
             a.borrow_mut().push(args[0].clone());
             Value::Null
         } else {
@@ -458,9 +2034,10 @@ pub fn array_methods() -> HashMap<String, StdMethod> {
     methods.insert("set".to_string(), |this: &Value, args: Vec<Value>| {
         if let Value::Array(a) = this {
             if let Value::Number(i) = args[0] {
-                let i = i as usize;
-                if i < a.borrow().len() {
-                    a.borrow_mut()[i] = args[1].clone();
+                a.borrow().ensure_mutable();
+                let length = a.borrow().len();
+                if let Some(index) = resolve_index(i, length) {
+                    a.borrow_mut()[index] = args[1].clone();
                     Value::Null
                 } else {
                     runtime_error(
@@ -484,15 +2061,10 @@ pub fn array_methods() -> HashMap<String, StdMethod> {
     methods.insert("get".to_string(), |this: &Value, args: Vec<Value>| {
         if let Value::Array(a) = this {
             if let Value::Number(i) = args[0] {
-                let i = i as i64;
                 // negative indices count from the end
-                let i = if i < 0 {
-                    a.borrow().len() as i64 + i
-                } else {
-                    i
-                };
-                if i >= 0 && i < a.borrow().len() as i64 {
-                    a.borrow()[i as usize].clone()
+                let length = a.borrow().len();
+                if let Some(index) = resolve_index(i, length) {
+                    a.borrow()[index].clone()
                 } else {
                     runtime_error(
                         format!(
@@ -514,6 +2086,7 @@ pub fn array_methods() -> HashMap<String, StdMethod> {
     });
     methods.insert("pop".to_string(), |this: &Value, _args: Vec<Value>| {
         if let Value::Array(a) = this {
+            a.borrow().ensure_mutable();
             if let Some(v) = a.borrow_mut().pop() {
                 v
             } else {
@@ -523,9 +2096,10 @@ pub fn array_methods() -> HashMap<String, StdMethod> {
             Value::Null // Unreachable
         }
     });
-    methods.insert("find".to_string(), |this: &Value, _args: Vec<Value>| {
+    // `find` and `index_of` are aliases, so both names share one function
+    let index_of: StdMethod = |this: &Value, args: Vec<Value>| {
         if let Value::Array(a) = this {
-            if let Some(i) = a.borrow().iter().position(|v| v == &_args[0]) {
+            if let Some(i) = a.borrow().iter().position(|v| deep_eq(v, &args[0])) {
                 Value::Number(i as f64)
             } else {
                 Value::Number(-1.)
@@ -533,6 +2107,67 @@ pub fn array_methods() -> HashMap<String, StdMethod> {
         } else {
             Value::Null // Unreachable
         }
+    };
+    methods.insert("find".to_string(), index_of);
+    methods.insert("index_of".to_string(), index_of);
+    methods.insert(
+        "last_index_of".to_string(),
+        |this: &Value, args: Vec<Value>| {
+            if let Value::Array(a) = this {
+                if let Some(i) = a.borrow().iter().rposition(|v| deep_eq(v, &args[0])) {
+                    Value::Number(i as f64)
+                } else {
+                    Value::Number(-1.)
+                }
+            } else {
+                Value::Null // Unreachable
+            }
+        },
+    );
+    methods.insert("contains".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::Array(a) = this {
+            Value::Boolean(a.borrow().iter().any(|v| deep_eq(v, &args[0])))
+        } else {
+            Value::Null // Unreachable
+        }
+    });
+    methods.insert("count".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::Array(a) = this {
+            let count = a.borrow().iter().filter(|v| deep_eq(v, &args[0])).count();
+            Value::Number(count as f64)
+        } else {
+            Value::Null // Unreachable
+        }
+    });
+    methods.insert("unique".to_string(), |this: &Value, _args: Vec<Value>| {
+        if let Value::Array(a) = this {
+            let mut unique: Vec<Value> = Vec::new();
+            for value in a.borrow().iter() {
+                if !unique.iter().any(|v| deep_eq(v, value)) {
+                    unique.push(value.clone());
+                }
+            }
+            Value::Array(Rc::new(RefCell::new(unique.into())))
+        } else {
+            Value::Null // Unreachable
+        }
+    });
+    methods.insert("reverse".to_string(), |this: &Value, _args: Vec<Value>| {
+        if let Value::Array(a) = this {
+            a.borrow().ensure_mutable();
+            a.borrow_mut().reverse();
+            this.clone()
+        } else {
+            Value::Null // Unreachable
+        }
+    });
+    methods.insert("reversed".to_string(), |this: &Value, _args: Vec<Value>| {
+        if let Value::Array(a) = this {
+            let reversed: Vec<Value> = a.borrow().iter().rev().cloned().collect();
+            Value::Array(Rc::new(RefCell::new(reversed.into())))
+        } else {
+            Value::Null // Unreachable
+        }
     });
     methods.insert("copy".to_string(), |this: &Value, _args: Vec<Value>| {
         if let Value::Array(a) = this {
@@ -542,15 +2177,320 @@ pub fn array_methods() -> HashMap<String, StdMethod> {
             Value::Null // Unreachable
         }
     });
+    methods.insert("slice".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::Array(a) = this {
+            let a = a.borrow();
+            let bound = |arg: Option<&Value>, default: usize| match arg {
+                None => default,
+                Some(Value::Number(i)) => clamp_index(*i, a.len()),
+                Some(other) => runtime_error(
+                    format!("Index must be a number in `slice` method: got {:?}", other).as_str(),
+                ),
+            };
+            let start = bound(args.first(), 0);
+            let end = bound(args.get(1), a.len());
+            let items = if start < end {
+                a[start..end].to_vec()
+            } else {
+                Vec::new()
+            };
+            Value::Array(Rc::new(RefCell::new(items.into())))
+        } else {
+            Value::Null // Unreachable
+        }
+    });
+    methods.insert("concat".to_string(), |this: &Value, args: Vec<Value>| {
+        if let (Value::Array(a), Value::Array(b)) = (this, &args[0]) {
+            let mut items = a.borrow().to_vec();
+            items.extend(b.borrow().iter().cloned());
+            Value::Array(Rc::new(RefCell::new(items.into())))
+        } else {
+            runtime_error(
+                format!(
+                    "`concat` method called with non-array argument: expected Array, got {:?}",
+                    args[0]
+                )
+                .as_str(),
+            )
+        }
+    });
+    methods.insert("resize".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::Array(a) = this {
+            let length = array_length_arg("resize", &args, 0);
+            a.borrow().ensure_mutable();
+            a.borrow_mut()
+                .resize(length, args.get(1).cloned().unwrap_or(Value::Null));
+            Value::Null
+        } else {
+            Value::Null // Unreachable
+        }
+    });
+    methods.insert("insert".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::Array(a) = this {
+            if let Value::Number(i) = args[0] {
+                a.borrow().ensure_mutable();
+                let length = a.borrow().len();
+                // Inserting at the length appends, while -1 still means before the last element
+                let slots = if i < 0.0 { length } else { length + 1 };
+                if let Some(index) = resolve_index(i, slots) {
+                    a.borrow_mut().insert(index, args[1].clone());
+                    Value::Null
+                } else {
+                    runtime_error(
+                        format!(
+                            "Index out of bounds in `insert` method: index {}, length {}",
+                            i, length,
+                        )
+                        .as_str(),
+                    )
+                }
+            } else {
+                runtime_error(
+                    format!(
+                        "Index must be a number in `insert` method: got {:?}",
+                        args[0],
+                    )
+                    .as_str(),
+                )
+            }
+        } else {
+            Value::Null // Unreachable
+        }
+    });
+    methods.insert("sum".to_string(), |this: &Value, _args: Vec<Value>| {
+        Value::Number(array_numbers("sum", this).iter().sum())
+    });
+    methods.insert("min".to_string(), |this: &Value, _args: Vec<Value>| {
+        let numbers = non_empty_array_numbers("min", this);
+        Value::Number(numbers.into_iter().fold(f64::INFINITY, f64::min))
+    });
+    methods.insert("max".to_string(), |this: &Value, _args: Vec<Value>| {
+        let numbers = non_empty_array_numbers("max", this);
+        Value::Number(numbers.into_iter().fold(f64::NEG_INFINITY, f64::max))
+    });
+    methods.insert("avg".to_string(), |this: &Value, _args: Vec<Value>| {
+        let numbers = non_empty_array_numbers("avg", this);
+        Value::Number(numbers.iter().sum::<f64>() / numbers.len() as f64)
+    });
+    methods.insert("join".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::Array(a) = this {
+            let separator = match args.first() {
+                None => ",",
+                Some(Value::String(s)) => s.as_str(),
+                Some(other) => runtime_error(
+                    format!("join separator must be a string: got {:?}", other).as_str(),
+                ),
+            };
+            let parts: Vec<String> = a.borrow().iter().map(|v| v.to_string()).collect();
+            Value::String(parts.join(separator))
+        } else {
+            Value::Null // Unreachable
+        }
+    });
+    methods
+}
+
+// The elements of an array that must contain only numbers, for the aggregate methods
+fn array_numbers(name: &str, this: &Value) -> Vec<f64> {
+    let Value::Array(a) = this else {
+        return Vec::new(); // Unreachable
+    };
+    a.borrow()
+        .iter()
+        .enumerate()
+        .map(|(i, v)| match v {
+            Value::Number(n) => *n,
+            other => runtime_error(
+                format!(
+                    "`{}` method requires an array of numbers: element {} is {}",
+                    name,
+                    i,
+                    other.type_name(),
+                )
+                .as_str(),
+            ),
+        })
+        .collect()
+}
+
+fn non_empty_array_numbers(name: &str, this: &Value) -> Vec<f64> {
+    let numbers = array_numbers(name, this);
+    if numbers.is_empty() {
+        runtime_error(format!("`{}` method called on an empty array", name).as_str());
+    }
+    numbers
+}
+
+pub fn array_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("length", 0, Some(0)),
+        ("remove", 1, Some(1)),
+        ("push", 1, Some(1)),
+        ("set", 2, Some(2)),
+        ("get", 1, Some(1)),
+        ("pop", 0, Some(0)),
+        ("find", 1, Some(1)),
+        ("index_of", 1, Some(1)),
+        ("last_index_of", 1, Some(1)),
+        ("contains", 1, Some(1)),
+        ("count", 1, Some(1)),
+        ("unique", 0, Some(0)),
+        ("reverse", 0, Some(0)),
+        ("reversed", 0, Some(0)),
+        ("copy", 0, Some(0)),
+        ("slice", 1, Some(2)),
+        ("concat", 1, Some(1)),
+        ("insert", 2, Some(2)),
+        ("resize", 1, Some(2)),
+        ("sum", 0, Some(0)),
+        ("min", 0, Some(0)),
+        ("max", 0, Some(0)),
+        ("avg", 0, Some(0)),
+        ("join", 0, Some(1)),
+    ])
+}
+
+pub fn array_evaluator_methods() -> HashMap<String, EvaluatorMethod> {
+    let mut methods: HashMap<String, EvaluatorMethod> = HashMap::new();
+
+    /*
+    Description of the methods:
+    - map: Returns a new array with the callback applied to every element.
+    - filter: Returns a new array with the elements the callback returns a truthy value for.
+    - reduce: Folds the array into a single value, starting from the given initial value.
+    - each: Calls the callback with every element.
+    - every: Returns true if the callback returns a truthy value for every element.
+    - some: Returns true if the callback returns a truthy value for at least one element.
+    */
+
+    methods.insert(
+        "map".to_string(),
+        |evaluator: &mut TreeWalk, this: &Value, args: Vec<Value>| {
+            let (items, callback) = array_and_callback("map", this, &args);
+            let mapped: Vec<Value> = items
+                .into_iter()
+                .map(|item| evaluator.call_function(&callback, vec![item]))
+                .collect();
+            Value::Array(Rc::new(RefCell::new(mapped.into())))
+        },
+    );
+    methods.insert(
+        "filter".to_string(),
+        |evaluator: &mut TreeWalk, this: &Value, args: Vec<Value>| {
+            let (items, callback) = array_and_callback("filter", this, &args);
+            let filtered: Vec<Value> = items
+                .into_iter()
+                .filter(|item| {
+                    evaluator
+                        .call_function(&callback, vec![item.clone()])
+                        .is_truthy()
+                })
+                .collect();
+            Value::Array(Rc::new(RefCell::new(filtered.into())))
+        },
+    );
+    methods.insert(
+        "reduce".to_string(),
+        |evaluator: &mut TreeWalk, this: &Value, args: Vec<Value>| {
+            let (items, callback) = array_and_callback("reduce", this, &args);
+            let initial = args[1].clone();
+            items.into_iter().fold(initial, |acc, item| {
+                evaluator.call_function(&callback, vec![acc, item])
+            })
+        },
+    );
+    methods.insert(
+        "each".to_string(),
+        |evaluator: &mut TreeWalk, this: &Value, args: Vec<Value>| {
+            let (items, callback) = array_and_callback("each", this, &args);
+            for item in items {
+                evaluator.call_function(&callback, vec![item]);
+            }
+            Value::Null
+        },
+    );
+    methods.insert(
+        "every".to_string(),
+        |evaluator: &mut TreeWalk, this: &Value, args: Vec<Value>| {
+            let (items, callback) = array_and_callback("every", this, &args);
+            Value::Boolean(
+                items
+                    .into_iter()
+                    .all(|item| evaluator.call_function(&callback, vec![item]).is_truthy()),
+            )
+        },
+    );
+    methods.insert(
+        "some".to_string(),
+        |evaluator: &mut TreeWalk, this: &Value, args: Vec<Value>| {
+            let (items, callback) = array_and_callback("some", this, &args);
+            Value::Boolean(
+                items
+                    .into_iter()
+                    .any(|item| evaluator.call_function(&callback, vec![item]).is_truthy()),
+            )
+        },
+    );
     methods
 }
 
+pub fn array_evaluator_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("map", 1, Some(1)),
+        ("filter", 1, Some(1)),
+        ("reduce", 2, Some(2)),
+        ("each", 1, Some(1)),
+        ("every", 1, Some(1)),
+        ("some", 1, Some(1)),
+    ])
+}
+
+// Snapshots the elements so the callback is free to modify the array while it is iterated
+fn array_and_callback(name: &str, this: &Value, args: &[Value]) -> (Vec<Value>, Value) {
+    let items = if let Value::Array(a) = this {
+        a.borrow().to_vec()
+    } else {
+        runtime_error(
+            format!(
+                "`{}` method called on non-array value: expected Array, got {:?}",
+                name, this,
+            )
+            .as_str(),
+        )
+    };
+    match args.first() {
+        Some(
+            callback @ (Value::Function { .. } | Value::RustFunction(..) | Value::Method { .. }),
+        ) => (items, callback.clone()),
+        other => runtime_error(
+            format!(
+                "`{}` method expects a function as its first argument: got {:?}",
+                name, other,
+            )
+            .as_str(),
+        ),
+    }
+}
+
 pub fn object_methods() -> HashMap<String, StdMethod> {
     let mut methods: HashMap<String, StdMethod> = HashMap::new();
 
+    /*
+    Description of the methods:
+    - set: Sets the property with the given name.
+    - get: Returns the property with the given name.
+    - keys: Returns an array of the property names, in the order they were defined.
+    - values: Returns an array of the property values, in the same order as keys.
+    - entries: Returns an array of [key, value] pairs, in the same order as keys.
+    - merge: Returns a new object with the properties of both, the argument's winning on conflicts.
+    - copy: Returns a shallow copy of the object, which is never frozen.
+    These are only reached when the object has no property of the same name.
+    */
+
     methods.insert("set".to_string(), |this: &Value, _args: Vec<Value>| {
         if let Value::Object(o) = this {
             if let Value::String(key) = &_args[0] {
+                o.borrow().ensure_mutable();
                 o.borrow_mut().insert(key.clone(), _args[1].clone());
                 Value::Null
             } else {
@@ -572,5 +2512,933 @@ pub fn object_methods() -> HashMap<String, StdMethod> {
             Value::Null // Unreachable
         }
     });
+    methods.insert("keys".to_string(), |this: &Value, _args: Vec<Value>| {
+        let keys: Vec<Value> = object_entries(this)
+            .into_iter()
+            .map(|(key, _)| Value::String(key))
+            .collect();
+        Value::Array(Rc::new(RefCell::new(keys.into())))
+    });
+    methods.insert("values".to_string(), |this: &Value, _args: Vec<Value>| {
+        let values: Vec<Value> = object_entries(this)
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect();
+        Value::Array(Rc::new(RefCell::new(values.into())))
+    });
+    methods.insert("entries".to_string(), |this: &Value, _args: Vec<Value>| {
+        let entries: Vec<Value> = object_entries(this)
+            .into_iter()
+            .map(|(key, value)| {
+                let pair = vec![Value::String(key), value];
+                Value::Array(Rc::new(RefCell::new(pair.into())))
+            })
+            .collect();
+        Value::Array(Rc::new(RefCell::new(entries.into())))
+    });
+    methods.insert("copy".to_string(), |this: &Value, _args: Vec<Value>| {
+        let Value::Object(o) = this else {
+            return Value::Null; // Unreachable
+        };
+        let copy = o.borrow().clone();
+        Value::Object(Rc::new(RefCell::new(copy)))
+    });
+    methods.insert("merge".to_string(), |this: &Value, args: Vec<Value>| {
+        if let (Value::Object(a), Value::Object(b)) = (this, &args[0]) {
+            let mut merged = a.borrow().clone();
+            for (key, value) in b.borrow().iter() {
+                merged.insert(key.clone(), value.clone());
+            }
+            Value::Object(Rc::new(RefCell::new(merged)))
+        } else {
+            runtime_error(
+                format!(
+                    "`merge` method called with non-object argument: expected Object, got {:?}",
+                    args[0]
+                )
+                .as_str(),
+            )
+        }
+    });
+    methods
+}
+
+// An object's properties in definition order, copied out so callers don't hold the borrow
+fn object_entries(this: &Value) -> Vec<(String, Value)> {
+    let Value::Object(o) = this else {
+        return Vec::new(); // Unreachable
+    };
+    o.borrow()
+        .iter()
+        .map(|(key, value)| (key.clone(), value.clone()))
+        .collect()
+}
+
+pub fn object_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("set", 2, Some(2)),
+        ("get", 1, Some(1)),
+        ("keys", 0, Some(0)),
+        ("values", 0, Some(0)),
+        ("entries", 0, Some(0)),
+        ("merge", 1, Some(1)),
+        ("copy", 0, Some(0)),
+    ])
+}
+
+pub fn map_methods() -> HashMap<String, StdMethod> {
+    let mut methods: HashMap<String, StdMethod> = HashMap::new();
+
+    /*
+    Description of the methods:
+    - get: Returns the value stored under the given key, or null if there is none.
+    - set: Stores a value under the given key.
+    - has: Returns whether the map has an entry for the given key.
+    - keys: Returns an array of the keys, in the order they were first set.
+    - values: Returns an array of the values, in the same order as keys.
+    - length: Returns the number of entries.
+    - copy: Returns a shallow copy of the map, which is never frozen.
+    Keys are numbers or strings, so 1 and "1" are different keys.
+    */
+
+    methods.insert("get".to_string(), |this: &Value, args: Vec<Value>| {
+        let key = map_key("get", &args[0]);
+        let Value::Map(m) = this else {
+            return Value::Null; // Unreachable
+        };
+        m.borrow().get(&key).cloned().unwrap_or(Value::Null)
+    });
+    methods.insert("set".to_string(), |this: &Value, args: Vec<Value>| {
+        let key = map_key("set", &args[0]);
+        if let Value::Map(m) = this {
+            m.borrow().ensure_mutable();
+            m.borrow_mut().insert(key, args[1].clone());
+        }
+        Value::Null
+    });
+    methods.insert("has".to_string(), |this: &Value, args: Vec<Value>| {
+        let key = map_key("has", &args[0]);
+        let Value::Map(m) = this else {
+            return Value::Null; // Unreachable
+        };
+        Value::Boolean(m.borrow().contains_key(&key))
+    });
+    methods.insert("keys".to_string(), |this: &Value, _args: Vec<Value>| {
+        let Value::Map(m) = this else {
+            return Value::Null; // Unreachable
+        };
+        let keys: Vec<Value> = m.borrow().keys().map(MapKey::to_value).collect();
+        Value::Array(Rc::new(RefCell::new(keys.into())))
+    });
+    methods.insert("values".to_string(), |this: &Value, _args: Vec<Value>| {
+        let Value::Map(m) = this else {
+            return Value::Null; // Unreachable
+        };
+        let values: Vec<Value> = m.borrow().values().cloned().collect();
+        Value::Array(Rc::new(RefCell::new(values.into())))
+    });
+    methods.insert("length".to_string(), |this: &Value, _args: Vec<Value>| {
+        let Value::Map(m) = this else {
+            return Value::Null; // Unreachable
+        };
+        Value::Number(m.borrow().len() as f64)
+    });
+    methods.insert("copy".to_string(), |this: &Value, _args: Vec<Value>| {
+        let Value::Map(m) = this else {
+            return Value::Null; // Unreachable
+        };
+        let copy = m.borrow().clone();
+        Value::Map(Rc::new(RefCell::new(copy)))
+    });
+    methods
+}
+
+pub fn map_key(name: &str, key: &Value) -> MapKey {
+    MapKey::from_value(key).unwrap_or_else(|| {
+        runtime_error(
+            format!(
+                "`{}` called with an invalid map key: expected a Number or String, got {}",
+                name, key,
+            )
+            .as_str(),
+        )
+    })
+}
+
+pub fn map_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("get", 1, Some(1)),
+        ("set", 2, Some(2)),
+        ("has", 1, Some(1)),
+        ("keys", 0, Some(0)),
+        ("values", 0, Some(0)),
+        ("length", 0, Some(0)),
+        ("copy", 0, Some(0)),
+    ])
+}
+
+pub fn set_methods() -> HashMap<String, StdMethod> {
+    let mut methods: HashMap<String, StdMethod> = HashMap::new();
+
+    /*
+    Description of the methods:
+    - add: Adds an element unless an equal one is already present.
+    - has: Returns whether the set contains an element equal to the argument.
+    - remove: Removes the element equal to the argument, returning whether there was one.
+    - union: Returns a new set with the elements of both sets.
+    - intersection: Returns a new set with the elements that are in both sets.
+    - difference: Returns a new set with the elements that are not in the argument.
+    - to_array: Returns the elements as an array, in the order they were added.
+    - length: Returns the number of elements.
+    - copy: Returns a shallow copy of the set, which is never frozen.
+    Elements are compared with ==, and results keep the order of the receiver's elements.
+    */
+
+    methods.insert("add".to_string(), |this: &Value, args: Vec<Value>| {
+        if let Value::Set(items) = this {
+            items.borrow().ensure_mutable();
+            items.borrow_mut().insert(args[0].clone());
+        }
+        Value::Null
+    });
+    methods.insert("has".to_string(), |this: &Value, args: Vec<Value>| {
+        let Value::Set(items) = this else {
+            return Value::Null; // Unreachable
+        };
+        Value::Boolean(items.borrow().contains(&args[0]))
+    });
+    methods.insert("remove".to_string(), |this: &Value, args: Vec<Value>| {
+        let Value::Set(items) = this else {
+            return Value::Null; // Unreachable
+        };
+        items.borrow().ensure_mutable();
+        let removed = items.borrow_mut().remove(&args[0]);
+        Value::Boolean(removed)
+    });
+    methods.insert("union".to_string(), |this: &Value, args: Vec<Value>| {
+        let (a, b) = set_operands("union", this, &args);
+        new_set(a.iter().chain(b.iter()).cloned().collect())
+    });
+    methods.insert(
+        "intersection".to_string(),
+        |this: &Value, args: Vec<Value>| {
+            let (a, b) = set_operands("intersection", this, &args);
+            new_set(a.iter().filter(|item| b.contains(item)).cloned().collect())
+        },
+    );
+    methods.insert(
+        "difference".to_string(),
+        |this: &Value, args: Vec<Value>| {
+            let (a, b) = set_operands("difference", this, &args);
+            new_set(a.iter().filter(|item| !b.contains(item)).cloned().collect())
+        },
+    );
+    methods.insert("to_array".to_string(), |this: &Value, _args: Vec<Value>| {
+        let Value::Set(items) = this else {
+            return Value::Null; // Unreachable
+        };
+        let items: Vec<Value> = items.borrow().iter().cloned().collect();
+        Value::Array(Rc::new(RefCell::new(items.into())))
+    });
+    methods.insert("length".to_string(), |this: &Value, _args: Vec<Value>| {
+        let Value::Set(items) = this else {
+            return Value::Null; // Unreachable
+        };
+        Value::Number(items.borrow().len() as f64)
+    });
+    methods.insert("copy".to_string(), |this: &Value, _args: Vec<Value>| {
+        let Value::Set(items) = this else {
+            return Value::Null; // Unreachable
+        };
+        let copy = items.borrow().clone();
+        Value::Set(Rc::new(RefCell::new(copy)))
+    });
     methods
 }
+
+fn new_set(items: OrderedSet) -> Value {
+    Value::Set(Rc::new(RefCell::new(items.into())))
+}
+
+// The receiver and argument of a binary set operation, copied so the same set can be on both sides
+fn set_operands(name: &str, this: &Value, args: &[Value]) -> (OrderedSet, OrderedSet) {
+    match (this, &args[0]) {
+        (Value::Set(a), Value::Set(b)) => ((**a.borrow()).clone(), (**b.borrow()).clone()),
+        _ => runtime_error(
+            format!(
+                "`{}` method called with non-set argument: expected Set, got {}",
+                name,
+                args[0].type_name(),
+            )
+            .as_str(),
+        ),
+    }
+}
+
+pub fn set_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("add", 1, Some(1)),
+        ("has", 1, Some(1)),
+        ("remove", 1, Some(1)),
+        ("union", 1, Some(1)),
+        ("intersection", 1, Some(1)),
+        ("difference", 1, Some(1)),
+        ("to_array", 0, Some(0)),
+        ("length", 0, Some(0)),
+        ("copy", 0, Some(0)),
+    ])
+}
+
+pub fn boolean_methods() -> HashMap<String, StdMethod> {
+    let mut methods: HashMap<String, StdMethod> = HashMap::new();
+
+    /*
+    Description of the methods:
+    - to_string: Converts the boolean to "true" or "false".
+    - not: Returns the negated boolean.
+    */
+
+    methods.insert(
+        "to_string".to_string(),
+        |this: &Value, _args: Vec<Value>| Value::String(this.to_string()),
+    );
+    methods.insert("not".to_string(), |this: &Value, _args: Vec<Value>| {
+        if let Value::Boolean(b) = this {
+            Value::Boolean(!b)
+        } else {
+            runtime_error(
+                format!(
+                    "`not` method called on non-boolean value: expected Boolean, got {:?}",
+                    this,
+                )
+                .as_str(),
+            )
+        }
+    });
+    methods
+}
+
+pub fn boolean_arities() -> HashMap<String, Arity> {
+    arities(&[("to_string", 0, Some(0)), ("not", 0, Some(0))])
+}
+
+pub fn universal_methods() -> HashMap<String, StdMethod> {
+    let mut methods: HashMap<String, StdMethod> = HashMap::new();
+
+    /*
+    Available on every value, including null. These are checked before the per-type methods.
+    Description of the methods:
+    - to_string: Converts the value to the string it prints as.
+    - type: Returns the name of the value's type, e.g. "Number".
+    - is_null: Returns whether the value is null.
+    */
+
+    methods.insert(
+        "to_string".to_string(),
+        |this: &Value, _args: Vec<Value>| Value::String(this.to_string()),
+    );
+    methods.insert("type".to_string(), |this: &Value, _args: Vec<Value>| {
+        Value::String(this.type_name().to_string())
+    });
+    methods.insert("is_null".to_string(), |this: &Value, _args: Vec<Value>| {
+        Value::Boolean(*this == Value::Null)
+    });
+    methods
+}
+
+pub fn universal_arities() -> HashMap<String, Arity> {
+    arities(&[
+        ("to_string", 0, Some(0)),
+        ("type", 0, Some(0)),
+        ("is_null", 0, Some(0)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn array(values: Vec<Value>) -> Value {
+        Value::Array(Rc::new(RefCell::new(values.into())))
+    }
+
+    fn numbers(values: &[f64]) -> Value {
+        array(values.iter().map(|&n| Value::Number(n)).collect())
+    }
+
+    fn call(
+        methods: &HashMap<String, StdMethod>,
+        name: &str,
+        this: &Value,
+        args: Vec<Value>,
+    ) -> Value {
+        methods[name](this, args)
+    }
+
+    #[test]
+    fn find_and_index_of_are_the_same_function() {
+        let methods = array_methods();
+        assert!(methods["find"] as usize == methods["index_of"] as usize);
+    }
+
+    #[test]
+    fn index_of_finds_first_and_last_duplicates() {
+        let methods = array_methods();
+        let values = numbers(&[4.0, 7.0, 4.0, 9.0, 4.0]);
+        for name in ["find", "index_of"] {
+            let index = call(&methods, name, &values, vec![Value::Number(4.0)]);
+            assert_eq!(index, Value::Number(0.0), "{}", name);
+        }
+        let last = call(&methods, "last_index_of", &values, vec![Value::Number(4.0)]);
+        assert_eq!(last, Value::Number(4.0));
+        let single = call(&methods, "last_index_of", &values, vec![Value::Number(9.0)]);
+        assert_eq!(single, Value::Number(3.0));
+    }
+
+    #[test]
+    fn index_of_returns_minus_one_when_absent() {
+        let methods = array_methods();
+        let values = numbers(&[1.0, 2.0]);
+        for name in ["find", "index_of", "last_index_of"] {
+            let index = call(&methods, name, &values, vec![Value::Number(3.0)]);
+            assert_eq!(index, Value::Number(-1.0), "{}", name);
+            let index = call(&methods, name, &numbers(&[]), vec![Value::Number(1.0)]);
+            assert_eq!(index, Value::Number(-1.0), "{} on an empty array", name);
+        }
+        let contains = call(&methods, "contains", &values, vec![Value::Number(3.0)]);
+        assert_eq!(contains, Value::Boolean(false));
+    }
+
+    #[test]
+    fn searches_compare_arrays_by_value() {
+        let methods = array_methods();
+        let values = array(vec![
+            numbers(&[1.0]),
+            numbers(&[1.0, 2.0]),
+            numbers(&[1.0, 2.0]),
+        ]);
+        let needle = numbers(&[1.0, 2.0]);
+        assert_eq!(
+            call(&methods, "index_of", &values, vec![needle.clone()]),
+            Value::Number(1.0)
+        );
+        assert_eq!(
+            call(&methods, "last_index_of", &values, vec![needle.clone()]),
+            Value::Number(2.0)
+        );
+        assert_eq!(
+            call(&methods, "contains", &values, vec![needle]),
+            Value::Boolean(true)
+        );
+    }
+
+    fn string(s: &str) -> Value {
+        Value::String(s.to_string())
+    }
+
+    fn number_method(name: &str, n: f64, args: &[f64]) -> Value {
+        let args = args.iter().map(|&arg| Value::Number(arg)).collect();
+        call(&number_methods(), name, &Value::Number(n), args)
+    }
+
+    #[test]
+    fn to_fixed_rounds_halves_up() {
+        assert_eq!(number_method("to_fixed", 2.675, &[2.0]), string("2.68"));
+        assert_eq!(number_method("to_fixed", 0.125, &[2.0]), string("0.13"));
+        assert_eq!(number_method("to_fixed", 2.5, &[0.0]), string("3"));
+        assert_eq!(number_method("to_fixed", -2.5, &[0.0]), string("-3"));
+        assert_eq!(number_method("to_fixed", 0.1 + 0.2, &[2.0]), string("0.30"));
+        assert_eq!(number_method("to_fixed", 1.0, &[3.0]), string("1.000"));
+    }
+
+    #[test]
+    fn to_precision_counts_significant_digits() {
+        assert_eq!(
+            number_method("to_precision", 123.456, &[4.0]),
+            string("123.5")
+        );
+        assert_eq!(number_method("to_precision", 0.5, &[3.0]), string("0.500"));
+        assert_eq!(
+            number_method("to_precision", 123456.0, &[2.0]),
+            string("1.2e+5")
+        );
+        assert_eq!(
+            number_method("to_precision", 0.000001234, &[2.0]),
+            string("0.0000012")
+        );
+    }
+
+    #[test]
+    fn to_string_with_a_radix() {
+        assert_eq!(number_method("to_string", 255.0, &[16.0]), string("ff"));
+        assert_eq!(number_method("to_string", 255.0, &[8.0]), string("377"));
+        assert_eq!(number_method("to_string", -5.0, &[2.0]), string("-101"));
+        assert_eq!(number_method("to_string", 2.5, &[]), string("2.5"));
+    }
+
+    #[test]
+    #[should_panic(expected = "`to_string` with a radix needs an integer: got 2.5")]
+    fn to_string_with_a_radix_rejects_fractions() {
+        number_method("to_string", 2.5, &[16.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "`to_string` radix must be 2, 8 or 16: got 10")]
+    fn to_string_rejects_other_radixes() {
+        number_method("to_string", 255.0, &[10.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "`to_fixed` digits must be an integer between 0 and 100: got -1")]
+    fn to_fixed_rejects_negative_digits() {
+        number_method("to_fixed", 1.5, &[-1.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "`to_fixed` digits must be an integer between 0 and 100: got 1.7")]
+    fn to_fixed_rejects_fractional_digits() {
+        number_method("to_fixed", 1.5, &[1.7]);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "`to_precision` digits must be an integer between 1 and 100: got 2.5"
+    )]
+    fn to_precision_rejects_fractional_digits() {
+        number_method("to_precision", 1.5, &[2.5]);
+    }
+
+    #[test]
+    fn factorial_and_cbrt() {
+        assert_eq!(number_method("factorial", 5.0, &[]), Value::Number(120.0));
+        assert_eq!(number_method("factorial", 0.0, &[]), Value::Number(1.0));
+        assert_eq!(number_method("cbrt", 27.0, &[]), Value::Number(3.0));
+        assert_eq!(number_method("cbrt", -8.0, &[]), Value::Number(-2.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "`factorial` method requires a non-negative integer: got -3")]
+    fn factorial_rejects_negative_numbers() {
+        number_method("factorial", -3.0, &[]);
+    }
+
+    #[test]
+    fn sleep_waits_at_least_the_duration() {
+        let start = std::time::Instant::now();
+        call(
+            &std_methods(),
+            "sleep",
+            &Value::Null,
+            vec![Value::Number(0.05)],
+        );
+        assert!(start.elapsed() >= std::time::Duration::from_millis(50));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "`sleep` duration must be a non-negative number of seconds: got 1e22"
+    )]
+    fn sleep_rejects_durations_too_long_to_represent() {
+        call(
+            &std_methods(),
+            "sleep",
+            &Value::Null,
+            vec![Value::Number(1e22)],
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "`sleep` duration must be a non-negative number of seconds: got -1")]
+    fn sleep_rejects_negative_durations() {
+        call(
+            &std_methods(),
+            "sleep",
+            &Value::Null,
+            vec![Value::Number(-1.0)],
+        );
+    }
+
+    fn process_result(stdout: &str, code: f64) -> Value {
+        let fields: ObjectMap = [
+            ("stdout", string(stdout)),
+            ("stderr", string("")),
+            ("code", Value::Number(code)),
+        ]
+        .into_iter()
+        .map(|(field, value)| (field.to_string(), value))
+        .collect();
+        Value::Object(Rc::new(RefCell::new(fields.into())))
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn exec_collects_output_and_exit_code() {
+        let result = call(
+            &std_methods(),
+            "exec",
+            &Value::Null,
+            vec![string("echo"), array(vec![string("hello")])],
+        );
+        assert_eq!(result, process_result("hello\n", 0.0));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn exec_reports_a_nonzero_exit_code() {
+        let result = call(
+            &std_methods(),
+            "exec",
+            &Value::Null,
+            vec![string("sh"), array(vec![string("-c"), string("exit 3")])],
+        );
+        assert_eq!(result, process_result("", 3.0));
+    }
+
+    #[test]
+    fn clock_advances_by_at_least_the_time_slept() {
+        let std = std_methods();
+        let clock = || match call(&std, "clock", &Value::Null, Vec::new()) {
+            Value::Number(seconds) => seconds,
+            other => panic!("clock returned {:?}", other),
+        };
+        let before = clock();
+        call(&std, "sleep", &Value::Null, vec![Value::Number(0.05)]);
+        assert!(clock() - before >= 0.05);
+    }
+
+    fn string_method(name: &str, s: &str, args: Vec<Value>) -> Value {
+        call(&string_methods(), name, &string(s), args)
+    }
+
+    #[test]
+    fn to_int_accepts_whole_numbers_and_prefixes() {
+        let to_int = |s: &str| string_method("to_int", s, Vec::new());
+        assert_eq!(to_int("42"), Value::Number(42.0));
+        assert_eq!(to_int(" 42 "), Value::Number(42.0));
+        assert_eq!(to_int("+5"), Value::Number(5.0));
+        assert_eq!(to_int("0xff"), Value::Number(255.0));
+        assert_eq!(to_int("-0x1f"), Value::Number(-31.0));
+        assert_eq!(to_int("0b101"), Value::Number(5.0));
+        assert_eq!(to_int("0o17"), Value::Number(15.0));
+    }
+
+    #[test]
+    fn to_int_is_null_for_anything_else() {
+        for s in ["0x", "", "abc", "12abc", "1 2", "3.9", "0xfg"] {
+            assert_eq!(
+                string_method("to_int", s, Vec::new()),
+                Value::Null,
+                "{:?}",
+                s
+            );
+        }
+    }
+
+    #[test]
+    fn to_float_and_is_number() {
+        let to_float = |s: &str| string_method("to_float", s, Vec::new());
+        assert_eq!(to_float(" 4.5 "), Value::Number(4.5));
+        assert_eq!(to_float("1e3"), Value::Number(1000.0));
+        assert_eq!(to_float("abc"), Value::Null);
+        assert_eq!(to_float("inf"), Value::Null);
+        let is_number = |s: &str| string_method("is_number", s, Vec::new());
+        assert_eq!(is_number(" 1"), Value::Boolean(true));
+        assert_eq!(is_number("-2.5"), Value::Boolean(true));
+        assert_eq!(is_number(""), Value::Boolean(false));
+        assert_eq!(is_number("0x"), Value::Boolean(false));
+    }
+
+    #[test]
+    fn parse_int_uses_the_given_radix() {
+        let parse_int =
+            |s: &str, radix: f64| string_method("parse_int", s, vec![Value::Number(radix)]);
+        assert_eq!(parse_int("ff", 16.0), Value::Number(255.0));
+        assert_eq!(parse_int("10", 16.0), Value::Number(16.0));
+        assert_eq!(parse_int("101", 2.0), Value::Number(5.0));
+        assert_eq!(parse_int("z", 36.0), Value::Number(35.0));
+        assert_eq!(parse_int("102", 2.0), Value::Null);
+        assert_eq!(parse_int("g", 16.0), Value::Null);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "Radix must be a whole number from 2 to 36 in `parse_int` method: got 37"
+    )]
+    fn parse_int_rejects_radixes_out_of_range() {
+        string_method("parse_int", "12", vec![Value::Number(37.0)]);
+    }
+
+    #[test]
+    fn to_int_rejects_fractions_that_to_float_keeps() {
+        assert_eq!(string_method("to_int", "3.9", Vec::new()), Value::Null);
+        assert_eq!(
+            string_method("to_float", "3.9", Vec::new()),
+            Value::Number(3.9)
+        );
+        assert_eq!(
+            string_method("to_int", "-3", Vec::new()),
+            Value::Number(-3.0)
+        );
+        assert_eq!(
+            string_method("to_float", "-3", Vec::new()),
+            Value::Number(-3.0)
+        );
+    }
+
+    #[test]
+    fn default_only_replaces_null() {
+        let default = |value: Value, fallback: Value| {
+            call(
+                &std_methods(),
+                "default",
+                &Value::Null,
+                vec![value, fallback],
+            )
+        };
+        assert_eq!(default(Value::Null, string("x")), string("x"));
+        assert_eq!(
+            default(Value::Number(0.0), Value::Number(5.0)),
+            Value::Number(0.0)
+        );
+        assert_eq!(default(string(""), string("x")), string(""));
+        assert_eq!(
+            default(Value::Boolean(false), Value::Boolean(true)),
+            Value::Boolean(false)
+        );
+    }
+
+    #[test]
+    fn enumerate_pairs_indices_with_values() {
+        let result = call(
+            &std_methods(),
+            "enumerate",
+            &Value::Null,
+            vec![array(vec![string("a"), string("b")])],
+        );
+        assert_eq!(
+            result,
+            array(vec![
+                array(vec![Value::Number(0.0), string("a")]),
+                array(vec![Value::Number(1.0), string("b")]),
+            ])
+        );
+        assert_eq!(
+            call(
+                &std_methods(),
+                "enumerate",
+                &Value::Null,
+                vec![array(vec![])]
+            ),
+            array(vec![])
+        );
+    }
+
+    #[test]
+    fn zip_stops_at_the_shorter_array() {
+        let zip = |a: &[f64], b: &[f64]| {
+            call(
+                &std_methods(),
+                "zip",
+                &Value::Null,
+                vec![numbers(a), numbers(b)],
+            )
+        };
+        let expected = array(vec![numbers(&[1.0, 4.0]), numbers(&[2.0, 5.0])]);
+        assert_eq!(zip(&[1.0, 2.0, 3.0], &[4.0, 5.0]), expected);
+        assert_eq!(zip(&[1.0, 2.0], &[4.0, 5.0, 6.0]), expected);
+        assert_eq!(zip(&[], &[1.0]), array(vec![]));
+    }
+
+    fn strings(values: &[&str]) -> Value {
+        array(values.iter().map(|s| string(s)).collect())
+    }
+
+    #[test]
+    fn split_without_a_separator_splits_on_whitespace() {
+        assert_eq!(
+            string_method("split", "  a \t b\n c ", Vec::new()),
+            strings(&["a", "b", "c"])
+        );
+        assert_eq!(
+            string_method("split", "  a  b  c", vec![Value::Null, Value::Number(2.0)]),
+            strings(&["a", "b  c"])
+        );
+        assert_eq!(string_method("split", "   ", Vec::new()), strings(&[]));
+    }
+
+    #[test]
+    fn split_with_an_empty_separator_splits_into_characters() {
+        assert_eq!(
+            string_method("split", "héllo", vec![string("")]),
+            strings(&["h", "é", "l", "l", "o"])
+        );
+        assert_eq!(
+            string_method("split", "abc", vec![string(""), Value::Number(2.0)]),
+            strings(&["a", "bc"])
+        );
+    }
+
+    #[test]
+    fn split_with_a_separator() {
+        assert_eq!(
+            string_method("split", "a,b,c", vec![string(","), Value::Number(2.0)]),
+            strings(&["a", "b,c"])
+        );
+        assert_eq!(
+            string_method("split", "a::b::::c", vec![string("::")]),
+            strings(&["a", "b", "", "c"])
+        );
+        assert_eq!(
+            string_method("split", "abc", vec![string(",")]),
+            strings(&["abc"])
+        );
+    }
+
+    #[test]
+    fn slice_and_substring_count_characters() {
+        for name in ["slice", "substring"] {
+            let slice = |s: &str, range: &[f64]| {
+                let args = range.iter().map(|&n| Value::Number(n)).collect();
+                string_method(name, s, args)
+            };
+            assert_eq!(slice("héllo", &[1.0, 3.0]), string("él"), "{}", name);
+            assert_eq!(slice("héllo", &[-3.0]), string("llo"), "{}", name);
+            assert_eq!(slice("héllo", &[2.0]), string("llo"), "{}", name);
+            assert_eq!(slice("héllo", &[-4.0, -2.0]), string("él"), "{}", name);
+        }
+    }
+
+    #[test]
+    fn slice_clamps_out_of_range_indices() {
+        let slice = |s: &str, range: &[f64]| {
+            let args = range.iter().map(|&n| Value::Number(n)).collect();
+            string_method("slice", s, args)
+        };
+        assert_eq!(slice("abc", &[1.0, 100.0]), string("bc"));
+        assert_eq!(slice("abc", &[-10.0, 2.0]), string("ab"));
+        assert_eq!(slice("abc", &[5.0]), string(""));
+        assert_eq!(slice("abc", &[2.0, 1.0]), string(""));
+    }
+
+    fn std_hash(value: Value) -> Value {
+        call(&std_methods(), "hash", &Value::Null, vec![value])
+    }
+
+    #[test]
+    fn hash_is_stable() {
+        // These are stored by scripts, so changing them breaks saved data
+        assert_eq!(std_hash(string("abc")), Value::Number(4380133153233835.0));
+        assert_eq!(
+            std_hash(Value::Number(1.0)),
+            Value::Number(1033869595876092.0)
+        );
+        assert_eq!(std_hash(Value::Null), Value::Number(7816128898336996.0));
+        assert_eq!(
+            std_hash(array(vec![Value::Number(1.0), string("a")])),
+            Value::Number(3954656375208175.0)
+        );
+    }
+
+    #[test]
+    fn deep_equal_values_hash_the_same() {
+        let nested = || array(vec![numbers(&[1.0, 2.0]), string("a")]);
+        assert_eq!(std_hash(nested()), std_hash(nested()));
+        assert_ne!(std_hash(nested()), std_hash(numbers(&[1.0, 2.0])));
+        assert_eq!(
+            std_hash(string("abc")),
+            string_method("hash", "abc", Vec::new())
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "crypto")]
+    fn sha256_matches_the_standard_digests() {
+        let sha256 = |s: &str| call(&std_methods(), "sha256", &Value::Null, vec![string(s)]);
+        assert_eq!(
+            sha256("abc"),
+            string("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+        );
+        assert_eq!(
+            sha256(""),
+            string("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855")
+        );
+    }
+
+    fn chr(code: f64) -> Value {
+        call(
+            &std_methods(),
+            "chr",
+            &Value::Null,
+            vec![Value::Number(code)],
+        )
+    }
+
+    #[test]
+    fn chr_and_ord_round_trip() {
+        let text = "Az~ é€😀";
+        for (i, c) in text.chars().enumerate() {
+            let code = string_method("ord", text, vec![Value::Number(i as f64)]);
+            assert_eq!(code, Value::Number(c as u32 as f64), "{}", c);
+            let Value::Number(code) = code else {
+                unreachable!()
+            };
+            assert_eq!(chr(code), string(&c.to_string()));
+            assert_eq!(
+                string_method("ord", &c.to_string(), Vec::new()),
+                Value::Number(code)
+            );
+        }
+        assert_eq!(
+            string_method("ord", "héllo", vec![Value::Number(-4.0)]),
+            Value::Number(233.0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "chr() called with invalid code point: 55296")]
+    fn chr_rejects_surrogates() {
+        chr(55296.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "chr() called with invalid code point: 1114112")]
+    fn chr_rejects_code_points_past_the_last() {
+        chr(1114112.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Index out of bounds in `ord` method: index 5, length 2")]
+    fn ord_rejects_indices_out_of_range() {
+        string_method("ord", "ab", vec![Value::Number(5.0)]);
+    }
+
+    #[test]
+    fn case_conversion_follows_unicode() {
+        assert_eq!(
+            string_method("upper", "straße", Vec::new()),
+            string("STRASSE")
+        );
+        assert_eq!(string_method("lower", "ÀB", Vec::new()), string("àb"));
+        assert_eq!(
+            string_method("capitalize", "élan vital", Vec::new()),
+            string("Élan vital")
+        );
+    }
+
+    #[test]
+    fn eq_ignore_case() {
+        let eq = |a: &str, b: &str| string_method("eq_ignore_case", a, vec![string(b)]);
+        assert_eq!(eq("Hello", "hELLO"), Value::Boolean(true));
+        assert_eq!(eq("Straße", "STRASSE"), Value::Boolean(true));
+        assert_eq!(eq("a", "b"), Value::Boolean(false));
+        assert_eq!(eq("", ""), Value::Boolean(true));
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "`upper` method called on non-string value: expected String, got Number(1.0)"
+    )]
+    fn case_conversion_rejects_non_strings() {
+        call(&string_methods(), "upper", &Value::Number(1.0), Vec::new());
+    }
+}
@@ -1,22 +1,32 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    ops::{Deref, DerefMut},
+    rc::Rc,
+};
 
 use crate::ast::ASTNode;
+use crate::treewalk::evaluator::runtime_error;
 
 pub type StdMethod = fn(&Value, Vec<Value>) -> Value; // Takes a receiver and arguments, returns a value
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Scope {
-    variables: HashMap<String, Value>,
+    // Kept in the order names were bound, so a module's namespace object lists them that way
+    variables: ObjectMap,
     parent: Option<Rc<RefCell<Scope>>>,
 }
 
 impl Scope {
     pub fn new(parent: Option<Rc<RefCell<Scope>>>) -> Self {
         Scope {
-            variables: HashMap::new(),
+            variables: ObjectMap::new(),
             parent,
         }
     }
+    /// Declares `name` in this scope, shadowing any binding of the same name in a parent.
     pub fn insert(&mut self, name: String, value: Value) {
         self.variables.insert(name, value);
     }
@@ -26,6 +36,24 @@ impl Scope {
             .cloned()
             .or_else(|| self.parent.as_ref()?.borrow().get(name))
     }
+    /// The bindings declared directly in this scope, in the order they were first bound. Named
+    /// functions are hoisted, so they come before the other declarations around them.
+    pub fn bindings(&self) -> Vec<(String, Value)> {
+        self.variables
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+    /// Number of scopes between this one and the global scope.
+    pub fn depth(&self) -> usize {
+        match &self.parent {
+            Some(parent) => parent.borrow().depth() + 1,
+            None => 0,
+        }
+    }
+    /// Assigns to an existing binding in the nearest scope that declares `name`, walking up the
+    /// parent chain. Closures share their defining scope, so they observe the change too.
+    /// Returns false if no scope declares `name`.
     pub fn set(&mut self, name: &str, value: Value) -> bool {
         if self.variables.contains_key(name) {
             self.variables.insert(name.to_string(), value);
@@ -38,34 +66,319 @@ impl Scope {
     }
 }
 
-pub fn object_to_string(obj: &Value) {
-    if let Value::Object(properties) = obj {
-        print!("{{");
-        for (i, (key, value)) in properties.borrow().iter().enumerate() {
-            print!("{}: ", key);
-            value.print();
-            if i < properties.borrow().len() - 1 {
-                print!(", ");
+/// Contents of an array or object, together with a flag marking it as frozen. Reads go straight
+/// through `Deref`; anything that mutates must call `ensure_mutable` first.
+#[derive(Debug, Default)]
+pub struct Freezable<T> {
+    inner: T,
+    frozen: bool,
+}
+
+impl<T> Freezable<T> {
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
+    pub fn freeze(&mut self) {
+        self.frozen = true;
+    }
+    pub fn ensure_mutable(&self) {
+        if self.frozen {
+            runtime_error("Cannot modify frozen object");
+        }
+    }
+}
+
+impl<T> From<T> for Freezable<T> {
+    fn from(inner: T) -> Self {
+        Freezable {
+            inner,
+            frozen: false,
+        }
+    }
+}
+
+// Copies start out unfrozen, only the original keeps its guarantee
+impl<T: Clone> Clone for Freezable<T> {
+    fn clone(&self) -> Self {
+        self.inner.clone().into()
+    }
+}
+
+impl<T: PartialEq> PartialEq for Freezable<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+    }
+}
+
+impl<T> Deref for Freezable<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T> DerefMut for Freezable<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+}
+
+/// Entries of an object or map, kept in the order they were first defined so printing and
+/// iteration are repeatable. Assigning to an existing key keeps its position.
+#[derive(Clone, Debug)]
+pub struct OrderedMap<K> {
+    entries: Vec<(K, Value)>,
+    index: HashMap<K, usize>,
+}
+
+/// Properties of an object, keyed by name.
+pub type ObjectMap = OrderedMap<String>;
+
+impl<K> Default for OrderedMap<K> {
+    fn default() -> Self {
+        OrderedMap {
+            entries: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash> OrderedMap<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn get<Q>(&self, key: &Q) -> Option<&Value>
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: std::borrow::Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.index.contains_key(key)
+    }
+    pub fn insert(&mut self, key: K, value: Value) {
+        match self.index.get(&key) {
+            Some(&i) => self.entries[i].1 = value,
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+            }
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &Value)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl<K: Clone + Eq + Hash> FromIterator<(K, Value)> for OrderedMap<K> {
+    fn from_iter<I: IntoIterator<Item = (K, Value)>>(iter: I) -> Self {
+        let mut map = OrderedMap::new();
+        for (key, value) in iter {
+            map.insert(key, value);
+        }
+        map
+    }
+}
+
+// Two objects or maps are equal when they have the same entries, whatever order they were
+// defined in
+impl<K: Clone + Eq + Hash> PartialEq for OrderedMap<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+/// Elements of a set, unique by `==` and kept in the order they were first added.
+#[derive(Clone, Debug, Default)]
+pub struct OrderedSet {
+    items: Vec<Value>,
+}
+
+impl OrderedSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn contains(&self, value: &Value) -> bool {
+        self.items.contains(value)
+    }
+    /// Adds `value` unless an equal element is already present. Returns whether it was added.
+    pub fn insert(&mut self, value: Value) -> bool {
+        if self.contains(&value) {
+            return false;
+        }
+        self.items.push(value);
+        true
+    }
+    /// Returns whether an element was removed.
+    pub fn remove(&mut self, value: &Value) -> bool {
+        let length = self.items.len();
+        self.items.retain(|item| item != value);
+        self.items.len() != length
+    }
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = &Value> {
+        self.items.iter()
+    }
+}
+
+impl FromIterator<Value> for OrderedSet {
+    fn from_iter<I: IntoIterator<Item = Value>>(iter: I) -> Self {
+        let mut set = OrderedSet::new();
+        for value in iter {
+            set.insert(value);
+        }
+        set
+    }
+}
+
+// Sets with the same elements are equal, whatever order they were added in
+impl PartialEq for OrderedSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|item| other.contains(item))
+    }
+}
+
+/// A key of a `Map`. Numbers are compared by value, with `0` and `-0` the same key; `nan` can't be
+/// a key since it isn't equal to itself.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum MapKey {
+    Number(u64),
+    String(String),
+}
+
+impl MapKey {
+    pub fn from_value(value: &Value) -> Option<MapKey> {
+        match value {
+            Value::Number(n) if n.is_nan() => None,
+            // Adding 0.0 turns -0.0 into 0.0
+            Value::Number(n) => Some(MapKey::Number((n + 0.0).to_bits())),
+            Value::String(s) => Some(MapKey::String(s.clone())),
+            _ => None,
+        }
+    }
+    pub fn to_value(&self) -> Value {
+        match self {
+            MapKey::Number(bits) => Value::Number(f64::from_bits(*bits)),
+            MapKey::String(s) => Value::String(s.clone()),
+        }
+    }
+}
+
+/// Freezes an array, object, map or set, and with `deep` everything reachable from it.
+/// Other values are already immutable and are left alone.
+pub fn freeze(value: &Value, deep: bool) {
+    match value {
+        Value::Array(a) => {
+            if a.borrow().is_frozen() {
+                return;
+            }
+            a.borrow_mut().freeze();
+            if deep {
+                for item in a.borrow().iter() {
+                    freeze(item, deep);
+                }
+            }
+        }
+        Value::Object(o) => {
+            if o.borrow().is_frozen() {
+                return;
+            }
+            o.borrow_mut().freeze();
+            if deep {
+                for item in o.borrow().values() {
+                    freeze(item, deep);
+                }
+            }
+        }
+        Value::Set(items) => {
+            if items.borrow().is_frozen() {
+                return;
+            }
+            items.borrow_mut().freeze();
+            if deep {
+                for item in items.borrow().iter() {
+                    freeze(item, deep);
+                }
+            }
+        }
+        Value::Map(m) => {
+            if m.borrow().is_frozen() {
+                return;
+            }
+            m.borrow_mut().freeze();
+            if deep {
+                for item in m.borrow().values() {
+                    freeze(item, deep);
+                }
             }
         }
-        print!("}}");
+        _ => {}
     }
 }
 
+pub fn object_to_string(obj: &Value) {
+    if let Value::Object(_) = obj {
+        obj.print();
+    }
+}
+
+/// A class declared with `class`. Its methods are closures over the scope the class was declared
+/// in, copied onto every instance so they are called with `this` bound to it.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Class {
+    pub name: String,
+    pub methods: ObjectMap,
+}
+
+// The derived `PartialEq` compares arrays and objects by content, since `Rc` and `RefCell`
+// both forward equality to the values they wrap.
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Clone, PartialEq, Debug)]
 pub enum Value {
     Number(f64),
     Boolean(bool),
     String(String),
     Return(Box<Value>),
-    Array(Rc<RefCell<Vec<Value>>>),
+    // Produced by `break` and `continue`, and passed up through blocks to the enclosing loop
+    // The label of the loop they target, if any
+    Break(Option<String>),
+    Continue(Option<String>),
+    Array(Rc<RefCell<Freezable<Vec<Value>>>>),
     Function {
         parameters: Vec<String>,
         body: Box<ASTNode>,
         env: Rc<RefCell<Scope>>,
     },
-    RustFunction(StdMethod),
-    Object(Rc<RefCell<HashMap<String, Value>>>),
+    RustFunction(String, StdMethod), // Qualified name such as "std.print", used in errors
+    Object(Rc<RefCell<Freezable<ObjectMap>>>),
+    Map(Rc<RefCell<Freezable<OrderedMap<MapKey>>>>),
+    Set(Rc<RefCell<Freezable<OrderedSet>>>),
+    Class(Rc<Class>),
     Method {
         receiver: Box<Value>,
         method_name: String,
@@ -73,6 +386,39 @@ pub enum Value {
     Null,
 }
 
+/// Recursively compares arrays and objects by value. Shared references short-circuit to `true`
+/// without walking their contents.
+pub fn deep_eq(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Array(x), Value::Array(y)) => {
+            if Rc::ptr_eq(x, y) {
+                return true;
+            }
+            let (x, y) = (x.borrow(), y.borrow());
+            x.len() == y.len() && x.iter().zip(y.iter()).all(|(a, b)| deep_eq(a, b))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            if Rc::ptr_eq(x, y) {
+                return true;
+            }
+            let (x, y) = (x.borrow(), y.borrow());
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, a)| y.get(key).is_some_and(|b| deep_eq(a, b)))
+        }
+        (Value::Map(x), Value::Map(y)) => {
+            if Rc::ptr_eq(x, y) {
+                return true;
+            }
+            let (x, y) = (x.borrow(), y.borrow());
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, a)| y.get(key).is_some_and(|b| deep_eq(a, b)))
+        }
+        _ => a == b,
+    }
+}
+
 impl Value {
     pub fn is_truthy(&self) -> bool {
         match self {
@@ -83,31 +429,289 @@ impl Value {
             _ => true,
         }
     }
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "Number",
+            Value::Boolean(_) => "Boolean",
+            Value::String(_) => "String",
+            Value::Return(_) => "Return",
+            Value::Break(_) => "Break",
+            Value::Continue(_) => "Continue",
+            Value::Array(_) => "Array",
+            Value::Function { .. } | Value::RustFunction(..) => "Function",
+            Value::Object(_) => "Object",
+            Value::Map(_) => "Map",
+            Value::Set(_) => "Set",
+            Value::Class(_) => "Class",
+            Value::Method { .. } => "Method",
+            Value::Null => "Null",
+        }
+    }
     pub fn print(&self) {
+        print!("{}", self);
+    }
+}
+
+// Largest integer an f64 can represent exactly, beyond this integers print in scientific form
+const MAX_SAFE_INTEGER: f64 = 9007199254740992.0;
+
+/// Formats a number the way PitLang prints it: integers without a decimal point, everything else
+/// with up to 15 significant digits and no trailing zeros.
+pub fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        return "nan".to_string();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "inf" } else { "-inf" }.to_string();
+    }
+    if n.fract() == 0.0 && n.abs() <= MAX_SAFE_INTEGER {
+        return format!("{}", n as i64);
+    }
+
+    let scientific = format!("{:.14e}", n);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    if (-5..15).contains(&exponent) {
+        let decimals = (14 - exponent) as usize;
+        trim_fraction(&format!("{:.*}", decimals, n)).to_string()
+    } else {
+        format!("{}e{}", trim_fraction(mantissa), exponent)
+    }
+}
+
+fn trim_fraction(s: &str) -> &str {
+    if s.contains('.') {
+        s.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        s
+    }
+}
+
+/// The user-facing form of a value, as `std.print` and `to_string()` show it.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Value::Number(n) => print!("{}", n),
-            Value::Boolean(b) => print!("{}", b),
-            Value::String(s) => print!("{}", s),
-            Value::Null => print!("null"),
+            Value::Number(n) => write!(f, "{}", format_number(*n)),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Null => write!(f, "null"),
             Value::Array(values) => {
-                print!("[");
+                write!(f, "[")?;
                 for (i, val) in values.borrow().iter().enumerate() {
-                    val.print();
-                    if i < values.borrow().len() - 1 {
-                        print!(", ");
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", val)?;
+                }
+                write!(f, "]")
+            }
+            Value::Object(properties) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in properties.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}: {}", key, value)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Map(entries) => {
+                write!(f, "Map {{")?;
+                for (i, (key, value)) in entries.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    // Quote string keys so 1 and "1" can be told apart
+                    match key {
+                        MapKey::String(key) => write!(f, "{:?}: {}", key, value)?,
+                        MapKey::Number(_) => write!(f, "{}: {}", key.to_value(), value)?,
                     }
                 }
-                print!("]");
+                write!(f, "}}")
             }
-            Value::Object(_) => object_to_string(self),
-            Value::Function { .. } => print!("Function"),
+            Value::Set(items) => {
+                write!(f, "Set {{")?;
+                for (i, item) in items.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                write!(f, "}}")
+            }
+            Value::Function { .. } => write!(f, "Function"),
+            Value::RustFunction(name, _) => write!(f, "Function {}", name),
+            Value::Class(class) => write!(f, "Class {}", class.name),
             Value::Method {
                 receiver,
                 method_name,
-            } => {
-                print!("Method: {:?}.{}", receiver, method_name)
-            }
-            _ => print!("Unsupported value"),
+            } => write!(f, "Method {}.{}", receiver.type_name(), method_name),
+            // Control signals never reach user code, but show something sensible when debugging
+            Value::Return(value) => write!(f, "{}", value),
+            Value::Break(_) => write!(f, "break"),
+            Value::Continue(_) => write!(f, "continue"),
+        }
+    }
+}
+
+/// Maps a possibly negative index onto `0..length`, counting negative indices from the end.
+pub fn resolve_index(index: f64, length: usize) -> Option<usize> {
+    let index = index as i64;
+    let index = if index < 0 {
+        length as i64 + index
+    } else {
+        index
+    };
+    if index >= 0 && index < length as i64 {
+        Some(index as usize)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn array(values: Vec<Value>) -> Value {
+        Value::Array(Rc::new(RefCell::new(values.into())))
+    }
+
+    fn object(entries: Vec<(&str, Value)>) -> Value {
+        let map: ObjectMap = entries
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        Value::Object(Rc::new(RefCell::new(map.into())))
+    }
+
+    fn point(x: f64, tags: Vec<Value>) -> Value {
+        object(vec![("x", Value::Number(x)), ("tags", array(tags))])
+    }
+
+    #[test]
+    fn distinct_but_structurally_equal_objects_are_equal() {
+        let a = point(1.0, vec![Value::String("a".to_string())]);
+        let b = point(1.0, vec![Value::String("a".to_string())]);
+        assert!(deep_eq(&a, &b));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn objects_differing_in_a_nested_value_are_not_equal() {
+        let a = point(1.0, vec![Value::Number(1.0)]);
+        let b = point(1.0, vec![Value::Number(2.0)]);
+        assert!(!deep_eq(&a, &b));
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn property_order_does_not_matter() {
+        let a = object(vec![("x", Value::Number(1.0)), ("y", Value::Null)]);
+        let b = object(vec![("y", Value::Null), ("x", Value::Number(1.0))]);
+        assert!(deep_eq(&a, &b));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn shared_references_are_equal() {
+        let shared = array(vec![Value::Number(1.0)]);
+        let a = object(vec![("items", shared.clone())]);
+        let b = object(vec![("items", shared.clone())]);
+        assert!(deep_eq(&shared, &shared.clone()));
+        assert!(deep_eq(&a, &b));
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn different_types_are_not_equal() {
+        assert!(!deep_eq(&array(Vec::new()), &object(Vec::new())));
+        assert!(!deep_eq(
+            &Value::Number(1.0),
+            &Value::String("1".to_string())
+        ));
+    }
+
+    #[test]
+    fn display_covers_every_variant() {
+        let string = |s: &str| Value::String(s.to_string());
+        let map: OrderedMap<MapKey> = [
+            (
+                MapKey::from_value(&Value::Number(1.0)).unwrap(),
+                string("number"),
+            ),
+            (MapKey::String("1".to_string()), string("string")),
+        ]
+        .into_iter()
+        .collect();
+        let set: OrderedSet = [Value::Number(1.0), string("a")].into_iter().collect();
+        let function = Value::Function {
+            parameters: vec!["x".to_string()],
+            body: Box::new(ASTNode::NullLiteral),
+            env: Rc::new(RefCell::new(Scope::new(None))),
+        };
+        let class = Class {
+            name: "Point".to_string(),
+            methods: ObjectMap::new(),
+        };
+        let cases = [
+            (Value::Number(3.0), "3"),
+            (Value::Boolean(true), "true"),
+            (string("text"), "text"),
+            (Value::Null, "null"),
+            (
+                array(vec![Value::Number(1.0), string("a"), array(vec![])]),
+                "[1, a, []]",
+            ),
+            (point(1.0, vec![Value::Null]), "{x: 1, tags: [null]}"),
+            (
+                Value::Map(Rc::new(RefCell::new(map.into()))),
+                "Map {1: number, \"1\": string}",
+            ),
+            (Value::Set(Rc::new(RefCell::new(set.into()))), "Set {1, a}"),
+            (function, "Function"),
+            (
+                Value::RustFunction("std.print".to_string(), |_, _| Value::Null),
+                "Function std.print",
+            ),
+            (Value::Class(Rc::new(class)), "Class Point"),
+            (
+                Value::Method {
+                    receiver: Box::new(string("a")),
+                    method_name: "upper".to_string(),
+                },
+                "Method String.upper",
+            ),
+            (Value::Return(Box::new(Value::Number(2.0))), "2"),
+            (Value::Break(None), "break"),
+            (Value::Continue(Some("outer".to_string())), "continue"),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(format!("{}", value), expected);
+        }
+    }
+
+    #[test]
+    fn number_formatting() {
+        let cases = [
+            (0.0, "0"),
+            (-0.0, "0"),
+            (42.0, "42"),
+            (-7.0, "-7"),
+            (9007199254740991.0, "9007199254740991"),
+            (0.1 + 0.2, "0.3"),
+            (1.5, "1.5"),
+            (-0.25, "-0.25"),
+            (1.0 / 3.0, "0.333333333333333"),
+            (0.00001, "0.00001"),
+            (1e21, "1e21"),
+            (1.5e-7, "1.5e-7"),
+            (f64::NAN, "nan"),
+            (f64::INFINITY, "inf"),
+            (f64::NEG_INFINITY, "-inf"),
+        ];
+        for (n, expected) in cases {
+            assert_eq!(format_number(n), expected, "{:?}", n);
         }
     }
 }
@@ -0,0 +1,284 @@
+mod common;
+
+use common::{eval, eval_error, global_after, runtime_error};
+use pitlang::treewalk::value::Value;
+
+#[test]
+fn join_writes_elements_the_way_print_does() {
+    assert_eq!(eval("[].join(\",\")"), eval("\"\""));
+    assert_eq!(eval("[1].join(\",\")"), eval("\"1\""));
+    assert_eq!(eval("[1, 2].join()"), eval("\"1,2\""));
+    assert_eq!(
+        eval("[1, \"a\", true, null, [2, 3], 1.5].join(\"-\")"),
+        eval("\"1-a-true-null-[2, 3]-1.5\"")
+    );
+}
+
+#[test]
+fn slice_and_concat_return_copies() {
+    let source = "
+        let a = [1, 2, 3];
+        let b = a.slice(0, 2);
+        b.push(9);
+        let c = a.concat([4]);
+        c.set(0, 0);
+    ";
+    assert_eq!(global_after(source, "a"), eval("[1, 2, 3]"));
+    assert_eq!(global_after(source, "b"), eval("[1, 2, 9]"));
+    assert_eq!(global_after(source, "c"), eval("[0, 2, 3, 4]"));
+}
+
+#[test]
+fn copies_are_shallow() {
+    let source = "
+        let a = [[1]];
+        a.concat([]).get(0).push(2);
+        a.slice(0).get(0).push(3);
+    ";
+    assert_eq!(global_after(source, "a"), eval("[[1, 2, 3]]"));
+}
+
+#[test]
+fn slice_clamps_its_bounds() {
+    assert_eq!(eval("[1, 2, 3].slice(-2)"), eval("[2, 3]"));
+    assert_eq!(eval("[1, 2, 3].slice(1, 100)"), eval("[2, 3]"));
+    assert_eq!(eval("[1, 2, 3].slice(5)"), eval("[]"));
+    assert_eq!(eval("[1, 2, 3].slice(2, 1)"), eval("[]"));
+}
+
+#[test]
+fn insert_accepts_indices_up_to_the_length() {
+    let source = "
+        let a = [1, 2];
+        a.insert(0, 0);
+        a.insert(3, 3);
+        a.insert(-1, 2.5);
+    ";
+    assert_eq!(global_after(source, "a"), eval("[0, 1, 2, 2.5, 3]"));
+    assert_eq!(
+        runtime_error("let a = [1, 2]; a.insert(5, 0);"),
+        "Index out of bounds in `insert` method: index 5, length 2"
+    );
+    assert_eq!(
+        runtime_error("let a = [1, 2]; a.insert(-3, 0);"),
+        "Index out of bounds in `insert` method: index -3, length 2"
+    );
+}
+
+#[test]
+fn sum_min_max_and_avg() {
+    assert_eq!(eval("[1, 2, 3].sum()"), eval("6"));
+    assert_eq!(eval("[].sum()"), eval("0"));
+    assert_eq!(eval("[1, 5, -3].min()"), eval("-3"));
+    assert_eq!(eval("[1, 5, -3].max()"), eval("5"));
+    assert_eq!(eval("[1, 2].avg()"), eval("1.5"));
+}
+
+#[test]
+fn min_max_and_avg_reject_empty_arrays() {
+    for method in ["min", "max", "avg"] {
+        assert_eq!(
+            eval_error(&format!("[].{}()", method)),
+            format!("`{}` method called on an empty array", method)
+        );
+    }
+}
+
+#[test]
+fn aggregates_reject_non_numbers() {
+    for method in ["sum", "min", "max", "avg"] {
+        assert_eq!(
+            eval_error(&format!("[1, \"a\"].{}()", method)),
+            format!(
+                "`{}` method requires an array of numbers: element 1 is String",
+                method
+            )
+        );
+    }
+}
+
+#[test]
+fn reverse_works_in_place_for_odd_and_even_lengths() {
+    let source = "
+        let odd = [1, 2, 3];
+        let returned = odd.reverse();
+        let even = [1, 2, 3, 4];
+        even.reverse();
+        let copy = even.reversed();
+    ";
+    assert_eq!(global_after(source, "odd"), eval("[3, 2, 1]"));
+    assert_eq!(global_after(source, "returned"), eval("[3, 2, 1]"));
+    assert_eq!(global_after(source, "even"), eval("[4, 3, 2, 1]"));
+    assert_eq!(global_after(source, "copy"), eval("[1, 2, 3, 4]"));
+    assert_eq!(eval("[].reverse()"), eval("[]"));
+}
+
+#[test]
+fn string_reverse_reverses_characters() {
+    assert_eq!(eval("\"héllo😀\".reverse()"), eval("\"😀olléh\""));
+    assert_eq!(eval("\"\".reverse()"), eval("\"\""));
+}
+
+#[test]
+fn unique_keeps_first_occurrences_by_value() {
+    assert_eq!(eval("[3, 1, 3, 2, 1].unique()"), eval("[3, 1, 2]"));
+    assert_eq!(
+        eval("[1, 2, 2, [1], [1], \"1\"].unique()"),
+        eval("[1, 2, [1], \"1\"]")
+    );
+    assert_eq!(eval("[].unique()"), eval("[]"));
+}
+
+#[test]
+fn count_compares_by_value() {
+    assert_eq!(eval("[1, 2, 2, [1], [1]].count(2)"), eval("2"));
+    assert_eq!(eval("[1, 2, 2, [1], [1]].count([1])"), eval("2"));
+    assert_eq!(eval("[1, \"1\"].count(\"1\")"), eval("1"));
+    assert_eq!(eval("[1].count(5)"), eval("0"));
+}
+
+#[test]
+fn every_and_some() {
+    let positive = "fn(x) { return x > 0; }";
+    for (array, every, some) in [
+        ("[1, 2, 3]", true, true),
+        ("[1, -2, 3]", false, true),
+        ("[-1, -2]", false, false),
+        ("[]", true, false),
+    ] {
+        assert_eq!(
+            eval(&format!("{}.every({})", array, positive)),
+            Value::Boolean(every),
+            "{}",
+            array
+        );
+        assert_eq!(
+            eval(&format!("{}.some({})", array, positive)),
+            Value::Boolean(some),
+            "{}",
+            array
+        );
+    }
+}
+
+#[test]
+fn every_and_some_stop_early() {
+    let source = "
+        let seen = [];
+        let check = fn(x) { seen.push(x); return x < 2; };
+        [1, 2, 3].every(check);
+        [5, 1, 0].some(check);
+    ";
+    assert_eq!(global_after(source, "seen"), eval("[1, 2, 5, 1]"));
+}
+
+#[test]
+fn resize_grows_shrinks_and_keeps_the_same_size() {
+    let source = "
+        let grown = [1, 2];
+        grown.resize(4, 0);
+        let padded = [1];
+        padded.resize(3);
+        let shrunk = [1, 2, 3];
+        shrunk.resize(1);
+        let same = [1, 2];
+        same.resize(2, 9);
+    ";
+    assert_eq!(global_after(source, "grown"), eval("[1, 2, 0, 0]"));
+    assert_eq!(global_after(source, "padded"), eval("[1, null, null]"));
+    assert_eq!(global_after(source, "shrunk"), eval("[1]"));
+    assert_eq!(global_after(source, "same"), eval("[1, 2]"));
+    assert_eq!(
+        eval_error("[1].resize(-1)"),
+        "`resize` length must be a non-negative whole number: got -1"
+    );
+}
+
+#[test]
+fn fill_repeats_one_value() {
+    assert_eq!(eval("std.fill(0, 3)"), eval("[0, 0, 0]"));
+    assert_eq!(eval("std.fill(\"a\", 0)"), eval("[]"));
+    let source = "
+        let rows = std.fill([], 2);
+        rows.get(0).push(1);
+    ";
+    assert_eq!(global_after(source, "rows"), eval("[[1], [1]]"));
+    assert_eq!(
+        eval_error("std.fill(0, 1.5)"),
+        "`fill` length must be a non-negative whole number: got 1.5"
+    );
+    assert_eq!(
+        eval_error("std.fill(0, 100000000)"),
+        "`fill` would create 100000000 elements, more than the limit of 10000000"
+    );
+}
+
+#[test]
+fn negative_indices_count_from_the_end() {
+    let source = "
+        let set = [1, 2, 3];
+        set.set(-1, 9);
+        set.set(-3, 7);
+        let removed = [1, 2, 3];
+        let last = removed.remove(-1);
+        removed.remove(-2);
+    ";
+    assert_eq!(global_after(source, "set"), eval("[7, 2, 9]"));
+    assert_eq!(global_after(source, "removed"), eval("[2]"));
+    assert_eq!(global_after(source, "last"), eval("3"));
+    assert_eq!(eval("\"héllo\".get(-1)"), eval("\"o\""));
+    assert_eq!(eval("\"héllo\".get(-4)"), eval("\"é\""));
+    assert_eq!(eval("\"héllo\".get(-5)"), eval("\"h\""));
+}
+
+#[test]
+fn indices_past_either_end_are_errors() {
+    for (expression, method, index, length) in [
+        ("[1].set(-2, 0)", "set", -2, 1),
+        ("[1].set(1, 0)", "set", 1, 1),
+        ("[1].remove(-2)", "remove", -2, 1),
+        ("\"ab\".get(-3)", "get", -3, 2),
+        ("\"ab\".get(2)", "get", 2, 2),
+    ] {
+        assert_eq!(
+            eval_error(expression),
+            format!(
+                "Index out of bounds in `{}` method: index {}, length {}",
+                method, index, length
+            )
+        );
+    }
+}
+
+#[test]
+fn range_with_each_arity() {
+    assert_eq!(eval("std.range(3)"), eval("[0, 1, 2]"));
+    assert_eq!(eval("std.range(2, 5)"), eval("[2, 3, 4]"));
+    assert_eq!(eval("std.range(0, 10, 3)"), eval("[0, 3, 6, 9]"));
+    assert_eq!(eval("std.range(0, 1, 0.25)"), eval("[0, 0.25, 0.5, 0.75]"));
+}
+
+#[test]
+fn range_counts_down_with_a_negative_step() {
+    assert_eq!(eval("std.range(5, 0, -2)"), eval("[5, 3, 1]"));
+    assert_eq!(eval("std.range(0, -3, -1)"), eval("[0, -1, -2]"));
+}
+
+#[test]
+fn range_is_empty_when_the_end_cannot_be_reached() {
+    assert_eq!(eval("std.range(0)"), eval("[]"));
+    assert_eq!(eval("std.range(5, 2)"), eval("[]"));
+    assert_eq!(eval("std.range(0, 5, -1)"), eval("[]"));
+}
+
+#[test]
+fn range_rejects_a_zero_step_and_huge_ranges() {
+    assert_eq!(
+        eval_error("std.range(0, 5, 0)"),
+        "range() step must be a non-zero number"
+    );
+    assert_eq!(
+        eval_error("std.range(100000000)"),
+        "range() would create 100000000 elements, more than the limit of 10000000"
+    );
+}
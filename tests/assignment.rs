@@ -0,0 +1,102 @@
+mod common;
+
+use common::{global_after, runtime_error};
+use pitlang::treewalk::value::Value;
+
+// `bucket()` and `list()` count their calls in `calls`, so each test can check the container
+// expression leading to the assigned place ran exactly once
+fn counted(statements: &str, name: &str) -> Value {
+    let source = format!(
+        "let calls = 0;
+        let store = {{hits: 1}};
+        let items = [10, 20];
+        fn bucket() {{ calls += 1; return store; }}
+        fn list() {{ calls += 1; return items; }}
+        {}",
+        statements
+    );
+    global_after(&source, name)
+}
+
+#[test]
+fn plain_assignment_evaluates_container_once() {
+    let statements = "bucket().hits = 5; list()[1] = 7;";
+    assert_eq!(counted(statements, "calls"), Value::Number(2.0));
+    assert_eq!(
+        counted(statements, "store"),
+        counted("store.hits = 5;", "store")
+    );
+    assert_eq!(
+        counted(statements, "items"),
+        counted("items[1] = 7;", "items")
+    );
+}
+
+#[test]
+fn compound_assignment_evaluates_container_once() {
+    let statements = "bucket().hits += 2; list()[1] -= 5;";
+    assert_eq!(counted(statements, "calls"), Value::Number(2.0));
+    assert_eq!(
+        counted(statements, "store"),
+        counted("store.hits = 3;", "store")
+    );
+    assert_eq!(
+        counted(statements, "items"),
+        counted("items[1] = 15;", "items")
+    );
+}
+
+#[test]
+fn increment_evaluates_container_once() {
+    let statements = "let old = bucket().hits++; let new = --list()[0];";
+    assert_eq!(counted(statements, "calls"), Value::Number(2.0));
+    assert_eq!(counted(statements, "old"), Value::Number(1.0));
+    assert_eq!(counted(statements, "new"), Value::Number(9.0));
+    assert_eq!(
+        counted(statements, "store"),
+        counted("store.hits = 2;", "store")
+    );
+}
+
+#[test]
+fn index_made_stale_by_right_hand_side_is_an_error() {
+    assert_eq!(
+        runtime_error("let a = [1, 2, 3]; a[2] = a.pop();"),
+        "Index out of bounds: index 2, length 2"
+    );
+    assert_eq!(
+        runtime_error("let a = [1, 2, 3]; fn shrink() { a.pop(); return 1; } a[2] += shrink();"),
+        "Index out of bounds: index 2, length 2"
+    );
+}
+
+#[test]
+fn postfix_returns_the_old_value_and_prefix_the_new_one() {
+    let source = "let i = 5; let a = i++; let j = 5; let b = ++j;";
+    assert_eq!(global_after(source, "a"), Value::Number(5.0));
+    assert_eq!(global_after(source, "i"), Value::Number(6.0));
+    assert_eq!(global_after(source, "b"), Value::Number(6.0));
+    assert_eq!(global_after(source, "j"), Value::Number(6.0));
+    let source = "let i = 5; let a = i--; let b = --i;";
+    assert_eq!(global_after(source, "a"), Value::Number(5.0));
+    assert_eq!(global_after(source, "b"), Value::Number(3.0));
+}
+
+#[test]
+fn postfix_on_member_and_index_targets() {
+    let source = "let o = {x: 1}; let arr = [10, 20];
+        let a = o.x++; let b = arr[0]--; let c = arr[-1]++;
+        let result = [a, b, c, o.x, arr[0], arr[1]];";
+    assert_eq!(
+        global_after(source, "result"),
+        global_after("let result = [1, 10, 20, 2, 9, 21];", "result")
+    );
+}
+
+#[test]
+fn increment_needs_a_number() {
+    assert_eq!(
+        runtime_error("let s = \"a\"; s++;"),
+        "Operand must be a number"
+    );
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::{eval, global_after, runtime_error};
+use pitlang::treewalk::value::Value;
+
+const POINT: &str = "
+    class Point {
+        fn constructor(x, y) {
+            this.x = x;
+            this.y = y;
+        }
+        fn distance(other) {
+            let dx = other.x - this.x;
+            let dy = other.y - this.y;
+            return std.math.sqrt(dx * dx + dy * dy);
+        }
+    }
+";
+
+fn with_point(statements: &str, name: &str) -> Value {
+    global_after(&format!("{} {}", POINT, statements), name)
+}
+
+#[test]
+fn constructor_binds_properties_on_the_instance() {
+    let p = with_point("let p = new Point(1, 2);", "p");
+    let Value::Object(properties) = p else {
+        panic!("expected an object, got {}", p);
+    };
+    assert_eq!(properties.borrow().get("x"), Some(&Value::Number(1.0)));
+    assert_eq!(properties.borrow().get("y"), Some(&Value::Number(2.0)));
+}
+
+#[test]
+fn methods_read_this() {
+    let result = with_point(
+        "let result = new Point(0, 0).distance(new Point(3, 4));",
+        "result",
+    );
+    assert_eq!(result, Value::Number(5.0));
+}
+
+#[test]
+fn instances_are_independent() {
+    let statements = "let a = new Point(1, 1); let b = new Point(2, 2); a.x = 10;
+        let result = [a.x, b.x];";
+    assert_eq!(with_point(statements, "result"), eval("[10, 2]"));
+}
+
+#[test]
+fn unknown_class_is_an_error() {
+    assert_eq!(
+        runtime_error("let p = new Missing(1);"),
+        "Undefined variable: Missing"
+    );
+    assert_eq!(
+        runtime_error("let NotAClass = 5; let p = new NotAClass();"),
+        "`new` expects a class: got Number"
+    );
+}
+
+#[test]
+fn calling_a_class_without_new_is_an_error() {
+    assert_eq!(
+        runtime_error(&format!("{} let p = Point(1, 2);", POINT)),
+        "Class 'Point' must be instantiated with `new Point(...)`"
+    );
+}
@@ -0,0 +1,51 @@
+// Each test crate uses a different subset of these helpers
+#![allow(dead_code)]
+
+use pitlang::errors::EvalError;
+use pitlang::parser;
+use pitlang::tokenizer;
+use pitlang::treewalk::evaluator::TreeWalk;
+use pitlang::treewalk::value::Value;
+
+// Runs `source` in a fresh evaluator, which is returned so tests can inspect its globals
+pub fn run(source: &str) -> (TreeWalk, Result<Value, EvalError>) {
+    let mut evaluator = TreeWalk::new(Vec::new());
+    let result = run_in(&mut evaluator, source);
+    (evaluator, result)
+}
+
+pub fn run_in(evaluator: &mut TreeWalk, source: &str) -> Result<Value, EvalError> {
+    let tokens = tokenizer::tokenize(source.to_string()).expect("tokenize");
+    let ast = parser::parse(&tokens).expect("parse");
+    evaluator.try_evaluate(ast)
+}
+
+// The value of the global `name` after running `source`, which must not error
+pub fn global_after(source: &str, name: &str) -> Value {
+    let (evaluator, result) = run(source);
+    if let Err(e) = result {
+        panic!("script failed: {}", e);
+    }
+    evaluator
+        .global(name)
+        .unwrap_or_else(|| panic!("no global '{}'", name))
+}
+
+// The runtime error message `source` stops with
+pub fn runtime_error(source: &str) -> String {
+    match run(source).1 {
+        Err(EvalError::Runtime(message)) => message,
+        Err(e) => panic!("expected a runtime error, got {}", e),
+        Ok(value) => panic!("expected a runtime error, got {}", value),
+    }
+}
+
+// The value of a single expression, which must not error
+pub fn eval(expression: &str) -> Value {
+    global_after(&format!("let result = {};", expression), "result")
+}
+
+// The runtime error message evaluating a single expression stops with
+pub fn eval_error(expression: &str) -> String {
+    runtime_error(&format!("let result = {};", expression))
+}
@@ -0,0 +1,41 @@
+mod common;
+
+use common::{eval, global_after, runtime_error};
+
+#[test]
+fn exact_length_binds_each_name() {
+    let source = "let [a, b] = [1, 2];";
+    assert_eq!(global_after(source, "a"), eval("1"));
+    assert_eq!(global_after(source, "b"), eval("2"));
+}
+
+#[test]
+fn rest_collects_the_remaining_elements() {
+    let source = "let [first, ...rest] = [1, 2, 3];";
+    assert_eq!(global_after(source, "first"), eval("1"));
+    assert_eq!(global_after(source, "rest"), eval("[2, 3]"));
+    assert_eq!(
+        global_after("let [a, b, ...rest] = [1, 2];", "rest"),
+        eval("[]")
+    );
+}
+
+#[test]
+fn too_short_is_an_error() {
+    assert_eq!(
+        runtime_error("let [a, b, c] = [1, 2];"),
+        "Cannot destructure array of length 2 into 3 names"
+    );
+    assert_eq!(
+        runtime_error("let [a, b, ...rest] = [1];"),
+        "Cannot destructure array of length 1 into 2 names"
+    );
+}
+
+#[test]
+fn too_long_is_an_error_without_a_rest_name() {
+    assert_eq!(
+        runtime_error("let [a, b] = [1, 2, 3];"),
+        "Cannot destructure array of length 3 into 2 names"
+    );
+}
@@ -0,0 +1,38 @@
+mod common;
+
+use common::global_after;
+use pitlang::treewalk::value::Value;
+
+fn check(source: &str) -> Value {
+    global_after(source, "result")
+}
+
+#[test]
+fn structurally_equal_objects_compare_equal() {
+    let setup = "let a = {x: 1, y: [1, {z: 2}]}; let b = {x: 1, y: [1, {z: 2}]};";
+    let both = format!("{} let result = [a == b, std.deep_eq(a, b)];", setup);
+    assert_eq!(check(&both), check("let result = [true, true];"));
+}
+
+#[test]
+fn changing_one_copy_makes_them_unequal() {
+    let source = "let a = {x: 1, y: [1, 2]}; let b = {x: 1, y: [1, 2]}; b.y.push(3);
+        let result = [a == b, std.deep_eq(a, b), a != b];";
+    assert_eq!(check(source), check("let result = [false, false, true];"));
+}
+
+#[test]
+fn shared_references_compare_equal() {
+    let source = "let inner = [1, 2]; let a = {items: inner}; let b = a; let c = {items: inner};
+        let result = [a == b, std.deep_eq(a, b), a == c, std.deep_eq(a, c)];";
+    assert_eq!(
+        check(source),
+        check("let result = [true, true, true, true];")
+    );
+}
+
+#[test]
+fn mutation_through_a_shared_reference_is_seen_by_both() {
+    let source = "let a = {n: 1}; let b = a; b.n = 2; let result = [a == b, a.n];";
+    assert_eq!(check(source), check("let result = [true, 2];"));
+}
@@ -0,0 +1,33 @@
+mod common;
+
+use common::{run, runtime_error};
+use pitlang::errors::EvalError;
+use std::process::Command;
+
+#[test]
+fn exit_code_becomes_the_process_status() {
+    let output = Command::new(env!("CARGO_BIN_EXE_pitlang"))
+        .arg(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/exit.pit"
+        ))
+        .output()
+        .expect("run pitlang");
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "before exit");
+    assert!(output.stderr.is_empty());
+}
+
+#[test]
+fn embedders_get_the_exit_code_back() {
+    assert!(matches!(run("std.exit(7);").1, Err(EvalError::Exit(7))));
+    assert!(matches!(run("std.exit();").1, Err(EvalError::Exit(0))));
+}
+
+#[test]
+fn exit_codes_must_be_whole_numbers_in_range() {
+    assert_eq!(
+        runtime_error("std.exit(256);"),
+        "exit() code must be a whole number from 0 to 255: got 256"
+    );
+}
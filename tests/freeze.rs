@@ -0,0 +1,116 @@
+mod common;
+
+use common::{global_after, runtime_error};
+
+const FROZEN: &str = "Cannot modify frozen object";
+
+fn assert_frozen(setup: &str, mutations: &[&str]) {
+    for mutation in mutations {
+        let source = format!("{} {}", setup, mutation);
+        assert_eq!(runtime_error(&source), FROZEN, "{}", mutation);
+    }
+}
+
+#[test]
+fn every_array_mutation_is_rejected() {
+    assert_frozen(
+        "let a = std.freeze([3, 1, 2]);",
+        &[
+            "a[0] = 5;",
+            "a[0] += 1;",
+            "a[0]++;",
+            "--a[1];",
+            "a.push(4);",
+            "a.pop();",
+            "a.set(0, 5);",
+            "a.remove(0);",
+            "a.insert(0, 5);",
+            "a.resize(5);",
+            "a.reverse();",
+            "std.shuffle(a);",
+        ],
+    );
+}
+
+#[test]
+fn every_object_mutation_is_rejected() {
+    assert_frozen(
+        "let o = std.freeze({count: 1});",
+        &[
+            "o.count = 2;",
+            "o.added = 2;",
+            "o[\"count\"] = 2;",
+            "o.count += 1;",
+            "o.count++;",
+            "o.set(\"count\", 2);",
+        ],
+    );
+}
+
+#[test]
+fn map_and_set_mutations_are_rejected() {
+    assert_frozen(
+        "let m = std.freeze(std.map());",
+        &["m.set(1, 2);", "m[1] = 2;"],
+    );
+    assert_frozen(
+        "let s = std.freeze(std.set([1]));",
+        &["s.add(2);", "s.remove(1);"],
+    );
+}
+
+#[test]
+fn reads_are_unaffected() {
+    let source = "let a = std.freeze([3, 1, 2]); let o = std.freeze({n: 1, list: a});
+        let result = [a[0], a.length(), a.reversed(), a.contains(2), o.n, o.keys(), o.list[2]];";
+    assert_eq!(
+        global_after(source, "result"),
+        global_after(
+            "let result = [3, 3, [2, 1, 3], true, 1, [\"n\", \"list\"], 2];",
+            "result"
+        )
+    );
+}
+
+#[test]
+fn freeze_is_shallow() {
+    let source = "let o = std.freeze({inner: {n: 1}, list: [1]});
+        o.inner.n = 2; o.list.push(2); let result = [o.inner.n, o.list.length()];";
+    assert_eq!(
+        global_after(source, "result"),
+        global_after("let result = [2, 2];", "result")
+    );
+}
+
+#[test]
+fn deep_freeze_reaches_nested_values() {
+    assert_frozen(
+        "let o = std.deep_freeze({inner: {n: 1}, list: [[1]]});",
+        &["o.inner.n = 2;", "o.list.push(2);", "o.list[0].push(2);"],
+    );
+}
+
+#[test]
+fn copies_of_frozen_values_are_mutable() {
+    let source = "let a = std.freeze([1]).copy(); a.push(2);
+        let o = std.freeze({n: 1}).copy(); o.n = 2;
+        let m = std.freeze(std.map()).copy(); m.set(1, 2);
+        let s = std.freeze(std.set()).copy(); s.add(1);
+        let result = [a.length(), o.n, m.length(), s.length()];";
+    assert_eq!(
+        global_after(source, "result"),
+        global_after("let result = [2, 2, 1, 1];", "result")
+    );
+}
+
+#[test]
+fn copying_leaves_the_original_frozen() {
+    assert_frozen(
+        "let o = std.freeze({n: 1}); let c = o.copy();",
+        &["o.n = 2;"],
+    );
+    assert_eq!(
+        global_after("let o = {n: 1}; let c = o.copy(); c.n = 2;", "o"),
+        global_after("let o = {n: 1};", "o")
+    );
+}
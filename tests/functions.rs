@@ -0,0 +1,102 @@
+mod common;
+
+use common::{global_after, runtime_error};
+use pitlang::treewalk::value::Value;
+
+#[test]
+fn mutually_recursive_functions_are_callable_before_their_declarations() {
+    let source = "
+        let even = isEven(10);
+        let odd = isOdd(7);
+        fn isEven(n) { if (n == 0) { return true; } return isOdd(n - 1); }
+        fn isOdd(n) { if (n == 0) { return false; } return isEven(n - 1); }
+    ";
+    assert_eq!(global_after(source, "even"), Value::Boolean(true));
+    assert_eq!(global_after(source, "odd"), Value::Boolean(true));
+}
+
+#[test]
+fn functions_nested_in_a_function_body_are_hoisted() {
+    let source = "
+        fn outer(n) {
+            let result = inner(n);
+            fn inner(x) { return helper(x) * 2; }
+            fn helper(x) { return x + 1; }
+            return result;
+        }
+        let result = outer(4);
+    ";
+    assert_eq!(global_after(source, "result"), Value::Number(10.0));
+}
+
+#[test]
+fn functions_in_a_block_are_hoisted_within_it() {
+    let source = "
+        let result = 0;
+        if (true) {
+            result = twice(3);
+            fn twice(x) { return x * 2; }
+        }
+    ";
+    assert_eq!(global_after(source, "result"), Value::Number(6.0));
+}
+
+#[test]
+fn returned_closures_can_still_reach_std() {
+    let source = "
+        fn make() {
+            return fn(x) {
+                std.println(x);
+                return std.math.sqrt(x);
+            };
+        }
+        let root = make();
+        let result = root(16);
+    ";
+    assert_eq!(global_after(source, "result"), Value::Number(4.0));
+}
+
+#[test]
+fn calling_with_the_wrong_number_of_arguments_names_the_function() {
+    let add = "fn add(a, b) { return a + b; }";
+    assert_eq!(
+        runtime_error(&format!("{} add(1);", add)),
+        "Function 'add' expected 2 arguments, got 1"
+    );
+    assert_eq!(
+        runtime_error(&format!("{} add(1, 2, 3);", add)),
+        "Function 'add' expected 2 arguments, got 3"
+    );
+}
+
+#[test]
+fn native_functions_check_their_arity() {
+    for (source, message) in [
+        (
+            "std.sleep();",
+            "Function 'std.sleep' expected 1 argument, got 0",
+        ),
+        (
+            "std.range();",
+            "Function 'std.range' expected 1 to 3 arguments, got 0",
+        ),
+        (
+            "std.math.sqrt(1, 2);",
+            "Function 'std.math.sqrt' expected 1 argument, got 2",
+        ),
+        (
+            "[1].push();",
+            "Method 'Array.push' expected 1 argument, got 0",
+        ),
+        (
+            "\"abc\".upper(1);",
+            "Method 'String.upper' expected 0 arguments, got 1",
+        ),
+        (
+            "\"abc\".split(\",\", 1, 2);",
+            "Method 'String.split' expected 0 to 2 arguments, got 3",
+        ),
+    ] {
+        assert_eq!(runtime_error(source), message, "{}", source);
+    }
+}
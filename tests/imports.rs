@@ -0,0 +1,90 @@
+mod common;
+
+use common::run_in;
+use pitlang::errors::EvalError;
+use pitlang::treewalk::evaluator::TreeWalk;
+use pitlang::treewalk::value::Value;
+
+// Runs `source` as if it were a script in tests/fixtures/imports, so its imports resolve there
+fn run_beside_fixtures(source: &str) -> (TreeWalk, Result<Value, EvalError>) {
+    let script = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/imports/main.pit"
+    );
+    let mut evaluator = TreeWalk::new(Vec::new()).with_script_path(script);
+    let result = run_in(&mut evaluator, source);
+    (evaluator, result)
+}
+
+fn global(source: &str, name: &str) -> Value {
+    let (evaluator, result) = run_beside_fixtures(source);
+    if let Err(e) = result {
+        panic!("script failed: {}", e);
+    }
+    evaluator.global(name).expect("global")
+}
+
+fn error(source: &str) -> String {
+    match run_beside_fixtures(source).1 {
+        Err(EvalError::Runtime(message)) => message,
+        other => panic!(
+            "expected a runtime error, got {:?}",
+            other.map(|v| v.to_string())
+        ),
+    }
+}
+
+#[test]
+fn imported_functions_and_variables_are_bound() {
+    let source = "import \"geometry.pit\"; let result = area(3, 4) + unit;";
+    assert_eq!(global(source, "result"), Value::Number(13.0));
+}
+
+#[test]
+fn a_file_is_only_run_once() {
+    let source = "let loads = 0; import \"loads_once.pit\"; import \"loads_once.pit\";";
+    assert_eq!(global(source, "loads"), Value::Number(1.0));
+}
+
+#[test]
+fn missing_file_is_an_error() {
+    let message = error("import \"missing.pit\";");
+    assert!(
+        message.starts_with("Cannot import 'missing.pit': "),
+        "{}",
+        message
+    );
+}
+
+#[test]
+fn cyclic_import_is_an_error() {
+    assert_eq!(
+        error("import \"cycle_a.pit\";"),
+        "Cyclic import: 'cycle_a.pit' is already being imported"
+    );
+}
+
+#[test]
+fn namespaces_keep_same_named_definitions_apart() {
+    let source = "import \"text.pit\" as text; import \"numbers.pit\" as numbers;
+        let result = [text.helper(), numbers.helper(), numbers.double(4)];";
+    assert_eq!(
+        global(source, "result"),
+        global(
+            "let result = [\"text helper\", \"numbers helper\", 8];",
+            "result"
+        )
+    );
+}
+
+#[test]
+fn namespace_properties_follow_definition_order() {
+    let source = "import \"text.pit\" as text; let result = text.keys();";
+    assert_eq!(
+        global(source, "result"),
+        global(
+            "let result = [\"helper\", \"name\", \"separator\"];",
+            "result"
+        )
+    );
+}
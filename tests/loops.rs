@@ -0,0 +1,81 @@
+mod common;
+
+use common::{eval, global_after};
+use pitlang::treewalk::value::Value;
+
+#[test]
+fn loop_else_is_skipped_after_break() {
+    let source = "
+        let ran = false;
+        for let i = 0; i < 5; i += 1 {
+            if i == 2 { break; }
+        } else {
+            ran = true;
+        }
+        let while_ran = false;
+        while true {
+            break;
+        } else {
+            while_ran = true;
+        }
+    ";
+    assert_eq!(global_after(source, "ran"), Value::Boolean(false));
+    assert_eq!(global_after(source, "while_ran"), Value::Boolean(false));
+}
+
+#[test]
+fn loop_else_runs_when_the_loop_finishes() {
+    let source = "
+        let ran = false;
+        for let i = 0; i < 5; i += 1 {
+            if i == 10 { break; }
+        } else {
+            ran = true;
+        }
+        let while_ran = false;
+        let n = 0;
+        while n < 3 {
+            n += 1;
+        } else {
+            while_ran = true;
+        }
+    ";
+    assert_eq!(global_after(source, "ran"), Value::Boolean(true));
+    assert_eq!(global_after(source, "while_ran"), Value::Boolean(true));
+}
+
+#[test]
+fn labeled_break_leaves_the_outer_loop() {
+    let source = "
+        let pairs = [];
+        outer: for let i = 0; i < 3; i += 1 {
+            for let j = 0; j < 3; j += 1 {
+                if i == 1 && j == 1 { break outer; }
+                pairs.push([i, j]);
+            }
+        }
+    ";
+    assert_eq!(
+        global_after(source, "pairs"),
+        eval("[[0, 0], [0, 1], [0, 2], [1, 0]]")
+    );
+}
+
+#[test]
+fn labeled_continue_moves_to_the_next_outer_iteration() {
+    let source = "
+        let pairs = [];
+        outer: for let i = 0; i < 3; i += 1 {
+            let j = 0;
+            while j < 3 {
+                if j == 1 { continue outer; }
+                pairs.push([i, j]);
+                j += 1;
+            }
+        }
+    ";
+    assert_eq!(
+        global_after(source, "pairs"),
+        eval("[[0, 0], [1, 0], [2, 0]]")
+    );
+}
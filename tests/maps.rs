@@ -0,0 +1,31 @@
+mod common;
+
+use common::{eval, global_after};
+
+#[test]
+fn number_and_string_keys_are_distinct() {
+    let source = "
+        let m = std.map();
+        m.set(1, \"number\");
+        m.set(\"1\", \"string\");
+        let length = m.length();
+        let number = m.get(1);
+        let text = m[\"1\"];
+    ";
+    assert_eq!(global_after(source, "length"), eval("2"));
+    assert_eq!(global_after(source, "number"), eval("\"number\""));
+    assert_eq!(global_after(source, "text"), eval("\"string\""));
+}
+
+#[test]
+fn setting_an_existing_key_overwrites_it_in_place() {
+    let source = "
+        let m = std.map([[\"a\", 1], [\"b\", 2]]);
+        m.set(\"a\", 10);
+        m[\"b\"] = 20;
+        let keys = m.keys();
+        let values = m.values();
+    ";
+    assert_eq!(global_after(source, "keys"), eval("[\"a\", \"b\"]"));
+    assert_eq!(global_after(source, "values"), eval("[10, 20]"));
+}
@@ -0,0 +1,48 @@
+mod common;
+
+use common::{eval, global_after, runtime_error};
+
+#[test]
+fn keys_values_and_entries_follow_definition_order() {
+    let object = "{b: 1, a: [2], c: null}";
+    assert_eq!(
+        eval(&format!("{}.keys()", object)),
+        eval("[\"b\", \"a\", \"c\"]")
+    );
+    assert_eq!(
+        eval(&format!("{}.values()", object)),
+        eval("[1, [2], null]")
+    );
+    assert_eq!(
+        eval(&format!("{}.entries()", object)),
+        eval("[[\"b\", 1], [\"a\", [2]], [\"c\", null]]")
+    );
+    assert_eq!(eval("{}.keys()"), eval("[]"));
+}
+
+#[test]
+fn merge_returns_a_new_object_with_the_argument_winning() {
+    let source = "
+        let p = {a: 1, b: 2};
+        let q = p.merge({b: 3, c: 4});
+    ";
+    assert_eq!(global_after(source, "p"), eval("{a: 1, b: 2}"));
+    assert_eq!(global_after(source, "q"), eval("{a: 1, b: 3, c: 4}"));
+}
+
+#[test]
+fn a_property_shadows_the_method_of_the_same_name() {
+    let source = "
+        let o = {keys: [9], b: 2};
+        let keys = o.keys;
+    ";
+    assert_eq!(global_after(source, "keys"), eval("[9]"));
+    assert_eq!(
+        runtime_error("let o = {keys: [9]}; o.keys();"),
+        "Called value is not a function: got Array"
+    );
+    assert_eq!(
+        eval("{keys: fn() { return \"own\"; }}.keys()"),
+        eval("\"own\"")
+    );
+}
@@ -0,0 +1,70 @@
+mod common;
+
+use common::{eval, eval_error, global_after};
+use pitlang::treewalk::value::Value;
+
+#[test]
+fn pipes_apply_functions_left_to_right() {
+    let source = "
+        fn inc(x) { return x + 1; }
+        fn dbl(x) { return x * 2; }
+        let result = 3 |> inc |> dbl;
+    ";
+    assert_eq!(global_after(source, "result"), eval("8"));
+}
+
+#[test]
+fn piping_into_a_call_inserts_the_first_argument() {
+    let source = "
+        fn sub(a, b) { return a - b; }
+        let piped = 10 |> sub(3);
+        let direct = sub(10, 3);
+    ";
+    assert_eq!(global_after(source, "piped"), eval("7"));
+    assert_eq!(global_after(source, "direct"), eval("7"));
+}
+
+#[test]
+fn not_follows_truthiness() {
+    assert_eq!(eval("!0"), Value::Boolean(true));
+    assert_eq!(eval("!\"\""), Value::Boolean(true));
+    assert_eq!(eval("!null"), Value::Boolean(true));
+    assert_eq!(eval("![1]"), Value::Boolean(false));
+    assert_eq!(eval("!1"), Value::Boolean(false));
+    assert_eq!(eval("!\"a\""), Value::Boolean(false));
+}
+
+#[test]
+fn plus_concatenates_when_either_side_is_a_string() {
+    assert_eq!(eval("\"x=\" + 5"), eval("\"x=5\""));
+    assert_eq!(eval("5 + \"x\""), eval("\"5x\""));
+    assert_eq!(eval("true + \"!\""), eval("\"true!\""));
+}
+
+#[test]
+fn plus_does_not_concatenate_arrays_with_strings() {
+    assert_eq!(
+        eval_error("[1] + \"a\""),
+        "Unsupported binary operation: Array Plus String"
+    );
+}
+
+#[test]
+fn logical_operators_short_circuit() {
+    assert_eq!(eval("false && undefined_var"), Value::Boolean(false));
+    assert_eq!(eval("true || undefined_var"), Value::Boolean(true));
+    assert_eq!(
+        eval_error("true && undefined_var"),
+        "Undefined variable: undefined_var"
+    );
+}
+
+#[test]
+fn null_coalescing_only_replaces_null() {
+    assert_eq!(eval("null ?? 5"), eval("5"));
+    assert_eq!(eval("0 ?? 5"), eval("0"));
+    assert_eq!(eval("\"\" ?? 5"), eval("\"\""));
+    assert_eq!(eval("false ?? 5"), Value::Boolean(false));
+    assert_eq!(eval("1 ?? undefined_var"), eval("1"));
+    assert_eq!(eval("null ?? null ?? 3"), eval("3"));
+}
@@ -0,0 +1,72 @@
+mod common;
+
+use common::{eval, global_after, runtime_error};
+
+#[test]
+fn assigning_from_a_nested_scope_updates_the_outer_variable() {
+    let source = "
+        let total = 0;
+        if true {
+            while total < 3 {
+                total = total + 1;
+            }
+        }
+    ";
+    assert_eq!(global_after(source, "total"), eval("3"));
+}
+
+#[test]
+fn closures_keep_their_own_counter() {
+    let source = "
+        fn counter() {
+            let count = 0;
+            return fn() {
+                count += 1;
+                return count;
+            };
+        }
+        let a = counter();
+        let b = counter();
+        a();
+        a();
+        let from_a = a();
+        let from_b = b();
+    ";
+    assert_eq!(global_after(source, "from_a"), eval("3"));
+    assert_eq!(global_after(source, "from_b"), eval("1"));
+}
+
+#[test]
+fn a_block_assigns_to_its_functions_variable() {
+    let source = "
+        fn f() {
+            let result = \"before\";
+            if true {
+                result = \"after\";
+            }
+            return result;
+        }
+        let result = f();
+    ";
+    assert_eq!(global_after(source, "result"), eval("\"after\""));
+}
+
+#[test]
+fn let_in_a_block_shadows_without_changing_the_outer_variable() {
+    let source = "
+        let x = 1;
+        if true {
+            let x = 2;
+            x = 3;
+        }
+    ";
+    assert_eq!(global_after(source, "x"), eval("1"));
+}
+
+#[test]
+fn assigning_an_undeclared_variable_is_an_error() {
+    assert_eq!(
+        runtime_error("fn f() { missing = 1; } f();"),
+        "Undefined variable: missing"
+    );
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{eval, global_after};
+
+#[test]
+fn construction_drops_duplicates() {
+    let source = "
+        let s = std.set([1, 2, 2, [3], [3], 1]);
+        let elements = s.to_array();
+    ";
+    assert_eq!(global_after(source, "elements"), eval("[1, 2, [3]]"));
+}
+
+#[test]
+fn set_operations() {
+    let a = "std.set([1, 2, 3])";
+    let b = "std.set([3, 4])";
+    assert_eq!(
+        eval(&format!("{}.union({}).to_array()", a, b)),
+        eval("[1, 2, 3, 4]")
+    );
+    assert_eq!(
+        eval(&format!("{}.intersection({}).to_array()", a, b)),
+        eval("[3]")
+    );
+    assert_eq!(
+        eval(&format!("{}.difference({}).to_array()", a, b)),
+        eval("[1, 2]")
+    );
+    assert_eq!(
+        eval(&format!("{}.union({})", b, a)),
+        eval("std.set([4, 3, 2, 1])")
+    );
+}
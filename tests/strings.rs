@@ -0,0 +1,94 @@
+mod common;
+
+use common::{eval, eval_error};
+use pitlang::treewalk::value::Value;
+
+#[test]
+fn contains_finds_substrings() {
+    assert_eq!(eval("\"hello\".contains(\"ell\")"), Value::Boolean(true));
+    assert_eq!(eval("\"hello\".contains(\"\")"), Value::Boolean(true));
+    assert_eq!(eval("\"hello\".contains(\"xyz\")"), Value::Boolean(false));
+    assert_eq!(eval("\"héllo\".contains(\"él\")"), Value::Boolean(true));
+    assert_eq!(
+        eval_error("\"hello\".contains(1)"),
+        "`contains` method called with non-string argument: expected String, got Number(1.0)"
+    );
+}
+
+#[test]
+fn substring_takes_character_positions() {
+    assert_eq!(eval("\"héllo\".substring(1, 2)"), eval("\"é\""));
+    assert_eq!(eval("\"héllo\".substring(1)"), eval("\"éllo\""));
+    assert_eq!(eval("\"hello\".substring(0, 0)"), eval("\"\""));
+}
+
+#[test]
+fn trim_strips_whitespace_by_default() {
+    assert_eq!(eval("\" \\t a b \\n\".trim()"), eval("\"a b\""));
+    assert_eq!(eval("\"  a \".trim_start()"), eval("\"a \""));
+    assert_eq!(eval("\"  a \".trim_end()"), eval("\"  a\""));
+}
+
+#[test]
+fn trim_with_a_charset_strips_any_of_its_characters() {
+    assert_eq!(eval("\"-=ab=-\".trim(\"-=\")"), eval("\"ab\""));
+    assert_eq!(eval("\"éaé\".trim(\"é\")"), eval("\"a\""));
+    assert_eq!(eval("\"xxx\".trim(\"x\")"), eval("\"\""));
+    assert_eq!(eval("\"xxaxx\".trim_start(\"x\")"), eval("\"axx\""));
+    assert_eq!(eval("\"xxaxx\".trim_end(\"x\")"), eval("\"xxa\""));
+}
+
+#[test]
+fn trim_leaves_trimmed_strings_alone() {
+    assert_eq!(eval("\"ab\".trim()"), eval("\"ab\""));
+    assert_eq!(eval("\"ab\".trim(\"x\")"), eval("\"ab\""));
+    assert_eq!(eval("\"\".trim()"), eval("\"\""));
+}
+
+#[test]
+fn prefix_and_suffix_checks() {
+    for (expression, expected) in [
+        ("\"abc\".starts_with(\"\")", true),
+        ("\"abc\".ends_with(\"\")", true),
+        ("\"abc\".contains(\"\")", true),
+        ("\"\".starts_with(\"a\")", false),
+        ("\"héllo\".starts_with(\"hé\")", true),
+        ("\"héllo\".ends_with(\"llo\")", true),
+        ("\"héllo\".ends_with(\"é\")", false),
+        ("\"😀x\".contains(\"😀\")", true),
+    ] {
+        assert_eq!(eval(expression), Value::Boolean(expected), "{}", expression);
+    }
+}
+
+#[test]
+fn prefix_and_suffix_checks_need_a_string() {
+    assert_eq!(
+        eval_error("\"abc\".starts_with(1)"),
+        "`starts_with` method called with non-string argument: expected String, got Number(1.0)"
+    );
+    assert_eq!(
+        eval_error("\"abc\".ends_with(null)"),
+        "`ends_with` method called with non-string argument: expected String, got Null"
+    );
+}
+
+#[test]
+fn chars_and_bytes_split_utf8() {
+    assert_eq!(eval("\"hé😀\".chars()"), eval("[\"h\", \"é\", \"😀\"]"));
+    assert_eq!(eval("\"hé\".bytes()"), eval("[104, 195, 169]"));
+    assert_eq!(eval("\"😀\".bytes().length()"), eval("4"));
+    assert_eq!(eval("\"\".chars()"), eval("[]"));
+    assert_eq!(eval("\"\".bytes()"), eval("[]"));
+}
+
+#[test]
+fn lines_handles_both_line_endings() {
+    assert_eq!(
+        eval("\"a\\r\\nb\\nc\".lines()"),
+        eval("[\"a\", \"b\", \"c\"]")
+    );
+    assert_eq!(eval("\"a\\n\".lines()"), eval("[\"a\"]"));
+    assert_eq!(eval("\"a\\n\\nb\".lines()"), eval("[\"a\", \"\", \"b\"]"));
+    assert_eq!(eval("\"\".lines()"), eval("[]"));
+}
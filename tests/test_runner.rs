@@ -0,0 +1,28 @@
+use std::process::{Command, Output};
+
+fn run_tests(fixture: &str) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_pitlang"))
+        .arg(format!(
+            "{}/tests/fixtures/{}",
+            env!("CARGO_MANIFEST_DIR"),
+            fixture
+        ))
+        .arg("-test")
+        .output()
+        .expect("run pitlang")
+}
+
+#[test]
+fn failing_test_is_reported_and_sets_exit_status() {
+    let output = run_tests("runner.pit");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout.lines().collect::<Vec<_>>(),
+        vec![
+            "PASS test_addition",
+            "FAIL test_wrong_sum: Runtime error: Assertion failed: 1 + 2 should be 4",
+            "1 passed, 1 failed",
+        ]
+    );
+}
@@ -0,0 +1,77 @@
+mod common;
+
+use common::run_in;
+use pitlang::treewalk::evaluator::{TraceEvent, TraceKind, TreeWalk};
+use pitlang::treewalk::value::Value;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// Runs `source` with a tracer that records every event
+fn trace(source: &str) -> Vec<TraceEvent> {
+    let events = Rc::new(RefCell::new(Vec::new()));
+    let mut evaluator = TreeWalk::new(Vec::new());
+    let sink = Rc::clone(&events);
+    evaluator.set_tracer(Box::new(move |event: &TraceEvent| {
+        sink.borrow_mut().push(event.clone())
+    }));
+    run_in(&mut evaluator, source).expect("script failed");
+    let events = events.borrow().clone();
+    events
+}
+
+fn event(kind: TraceKind, node: &'static str, depth: usize) -> TraceEvent {
+    TraceEvent {
+        kind,
+        node,
+        position: None,
+        depth,
+    }
+}
+
+#[test]
+fn tracer_sees_statements_calls_returns_and_assignments() {
+    let events = trace("fn add(a, b) { return a + b; }\nlet x = add(1, 2);\nx = 5;");
+    let number = Value::Number;
+    assert_eq!(
+        events,
+        vec![
+            event(TraceKind::Statement, "FunctionDeclaration", 0),
+            event(TraceKind::Statement, "VariableDeclaration", 0),
+            event(
+                TraceKind::Call {
+                    callee: "add".to_string(),
+                    arguments: vec![number(1.0), number(2.0)],
+                },
+                "FunctionCall",
+                0,
+            ),
+            // Inside the function's scope and its body's block
+            event(TraceKind::Statement, "ReturnStatement", 2),
+            event(
+                TraceKind::Return {
+                    callee: "add".to_string(),
+                    value: number(3.0),
+                },
+                "FunctionCall",
+                0,
+            ),
+            event(
+                TraceKind::Assignment {
+                    name: "x".to_string(),
+                    value: number(3.0),
+                },
+                "VariableDeclaration",
+                0,
+            ),
+            event(TraceKind::Statement, "BinaryOp", 0),
+            event(
+                TraceKind::Assignment {
+                    name: "x".to_string(),
+                    value: number(5.0),
+                },
+                "BinaryOp",
+                0,
+            ),
+        ]
+    );
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::run_in;
+use pitlang::common::Warning;
+use pitlang::errors::EvalError;
+use pitlang::treewalk::evaluator::TreeWalk;
+
+fn warnings_for(source: &str) -> (Vec<Warning>, Result<(), EvalError>) {
+    let mut evaluator = TreeWalk::new(Vec::new()).with_warnings(true);
+    let result = run_in(&mut evaluator, source).map(|_| ());
+    (evaluator.take_warnings(), result)
+}
+
+#[test]
+fn fixture_script_warnings() {
+    let (warnings, result) = warnings_for(include_str!("fixtures/warnings.pit"));
+    assert!(matches!(result, Err(EvalError::Runtime(_))));
+    assert_eq!(
+        warnings,
+        vec![
+            Warning::new(
+                "Array.find is given a String, but the array holds no String values",
+                Some((2, 27))
+            ),
+            Warning::new(
+                "Equality comparison between Number and String is always false",
+                Some((3, 22))
+            ),
+            Warning::new("Calling 'callback', which is Null", Some((7, 9))),
+        ]
+    );
+}
+
+#[test]
+fn mixed_ordering_comparison_warns_before_the_error() {
+    let (warnings, result) = warnings_for("let x = 1;\nlet y = x < \"2\";");
+    assert!(matches!(result, Err(EvalError::Runtime(_))));
+    assert_eq!(
+        warnings,
+        vec![Warning::new(
+            "Ordering comparison between Number and String",
+            Some((2, 11))
+        )]
+    );
+}
+
+#[test]
+fn argument_count_mismatch_warns_before_the_error() {
+    let (warnings, result) = warnings_for("fn add(a, b) { return a + b; }\nadd(1);");
+    assert!(matches!(result, Err(EvalError::Runtime(_))));
+    assert_eq!(
+        warnings,
+        vec![Warning::new(
+            "Function 'add' expected 2 arguments, got 1",
+            Some((2, 4))
+        )]
+    );
+}
+
+#[test]
+fn no_warnings_unless_enabled() {
+    let mut evaluator = TreeWalk::new(Vec::new());
+    let _ = run_in(&mut evaluator, "let same = 1 == \"1\";");
+    assert!(evaluator.take_warnings().is_empty());
+}